@@ -1,6 +1,12 @@
 use core::fmt;
+use std::cell::RefCell;
 use std::cmp::PartialEq;
+use std::collections::BTreeMap;
 use std::fmt::Display;
+use std::rc::Rc;
+
+use crate::error::RuntimeError;
+use crate::function::{Function, NativeFunction};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum TokenType {
@@ -8,8 +14,11 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
+    Colon,
     Minus,
     Plus,
     Semicolon,
@@ -25,6 +34,7 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    Ellipsis,
     // LiteralValues.
     Identifier,
     String,
@@ -47,17 +57,26 @@ pub enum TokenType {
     True,
     Var,
     While,
+    Break,
+    Continue,
+    Throw,
+    Try,
+    Catch,
+    Finally,
+    Import,
+    From,
+    As,
 
     // bitwise
     BitwiseAnd,
     BitwiseOr,
 
-    EOF,
+    Eof,
 }
 
 impl Display for TokenType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{0}", &self)
+        write!(f, "{0:?}", &self)
     }
 }
 
@@ -68,30 +87,68 @@ pub enum LiteralValue {
     String(String),
     Float(f64),
     Integer(i64),
+    Function(Rc<Function>),
+    NativeFunction(Rc<NativeFunction>),
+    List(Rc<RefCell<Vec<LiteralValue>>>),
+    Map(Rc<RefCell<BTreeMap<String, LiteralValue>>>),
+    Error(Rc<RuntimeError>),
+}
+impl LiteralValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            LiteralValue::None => "nil",
+            LiteralValue::Bool(_) => "bool",
+            LiteralValue::String(_) => "string",
+            LiteralValue::Float(_) => "float",
+            LiteralValue::Integer(_) => "int",
+            LiteralValue::Function(_) | LiteralValue::NativeFunction(_) => "function",
+            LiteralValue::List(_) => "list",
+            LiteralValue::Map(_) => "map",
+            LiteralValue::Error(_) => "error",
+        }
+    }
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            LiteralValue::None => false,
+            LiteralValue::Bool(value) => *value,
+            LiteralValue::Integer(value) => *value != 0,
+            LiteralValue::String(value) => !value.is_empty(),
+            LiteralValue::Float(value) => *value != 0.0,
+            LiteralValue::Function(_) | LiteralValue::NativeFunction(_) => true,
+            LiteralValue::List(items) => !items.borrow().is_empty(),
+            LiteralValue::Map(entries) => !entries.borrow().is_empty(),
+            LiteralValue::Error(_) => true,
+        }
+    }
 }
 impl PartialEq for LiteralValue {
     fn eq(&self, other: &Self) -> bool {
-        match self {
-            LiteralValue::None => match other {
-                LiteralValue::None => true,
-                _ => false,
-            },
-            LiteralValue::String(value) => match other {
-                LiteralValue::String(other_value) => value == other_value,
-                _ => false,
-            },
-            LiteralValue::Integer(value) => match other {
-                LiteralValue::Integer(other_value) => value == other_value,
-                _ => false,
-            },
-            LiteralValue::Bool(value) => match other {
-                LiteralValue::Bool(other_value) => value == other_value,
-                _ => false,
-            },
-            LiteralValue::Float(value) => match other {
-                LiteralValue::Float(other_value) => value == other_value,
-                _ => false,
-            },
+        match (self, other) {
+            (LiteralValue::None, LiteralValue::None) => true,
+            (LiteralValue::String(value), LiteralValue::String(other_value)) => {
+                value == other_value
+            }
+            (LiteralValue::Integer(value), LiteralValue::Integer(other_value)) => {
+                value == other_value
+            }
+            (LiteralValue::Bool(value), LiteralValue::Bool(other_value)) => value == other_value,
+            (LiteralValue::Float(value), LiteralValue::Float(other_value)) => value == other_value,
+            (LiteralValue::Function(value), LiteralValue::Function(other_value)) => {
+                Rc::ptr_eq(value, other_value)
+            }
+            (LiteralValue::NativeFunction(value), LiteralValue::NativeFunction(other_value)) => {
+                Rc::ptr_eq(value, other_value)
+            }
+            (LiteralValue::List(value), LiteralValue::List(other_value)) => {
+                *value.borrow() == *other_value.borrow()
+            }
+            (LiteralValue::Map(value), LiteralValue::Map(other_value)) => {
+                *value.borrow() == *other_value.borrow()
+            }
+            (LiteralValue::Error(value), LiteralValue::Error(other_value)) => {
+                Rc::ptr_eq(value, other_value)
+            }
+            _ => false,
         }
     }
 }
@@ -113,28 +170,74 @@ impl fmt::Display for LiteralValue {
             LiteralValue::None => {
                 write!(f, "nil")
             }
+            LiteralValue::Function(function) => {
+                write!(f, "{}", function)
+            }
+            LiteralValue::NativeFunction(function) => {
+                write!(f, "{}", function)
+            }
+            LiteralValue::List(items) => {
+                let items: Vec<String> =
+                    items.borrow().iter().map(|item| item.to_string()).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+            LiteralValue::Map(entries) => {
+                let entries: Vec<String> = entries
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, value))
+                    .collect();
+                write!(f, "{{{}}}", entries.join(", "))
+            }
+            LiteralValue::Error(error) => {
+                write!(f, "{}", error.message)
+            }
         }
     }
 }
+// Source text between tokens. Only scanners that keep trivia record it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TriviaKind {
+    Whitespace,
+    Newline,
+    LineComment,
+    BlockComment,
+}
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+}
+
 #[derive(Clone, Debug)]
 pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
     pub literal_value: LiteralValue,
-    pub line: u8,
+    pub line: usize,
+    // Where the lexeme starts in the source, in bytes.
+    pub offset: usize,
+    // A token owns the trivia after it up to the end of its line; everything
+    // else before the next token, newlines included, leads that token. Boxed
+    // slices keep tokens small, since the parser clones them freely.
+    pub leading_trivia: Box<[Trivia]>,
+    pub trailing_trivia: Box<[Trivia]>,
 }
 impl Token {
     pub fn new(
         token_type: TokenType,
         lexeme: String,
         literal_value: LiteralValue,
-        line: u8,
+        line: usize,
     ) -> Self {
         Token {
             token_type,
             lexeme,
             literal_value,
             line,
+            offset: 0,
+            leading_trivia: Box::new([]),
+            trailing_trivia: Box::new([]),
         }
     }
 }
@@ -143,7 +246,7 @@ impl Display for Token {
         write!(f, "{0} {1}", &self.token_type, &self.lexeme,)
     }
 }
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub enum Expr {
     Binary {
         left: Box<Expr>,
@@ -167,12 +270,113 @@ pub enum Expr {
         name: Token,
         value: Box<Expr>,
     },
+    Logical {
+        left: Box<Expr>,
+        operator: Token,
+        right: Box<Expr>,
+    },
+    Call {
+        callee: Box<Expr>,
+        paren: Token,
+        arguments: Vec<Expr>,
+        keyword_arguments: Vec<(Token, Expr)>,
+    },
+    Lambda {
+        params: Vec<Param>,
+        body: Vec<Statement>,
+    },
+    Get {
+        object: Box<Expr>,
+        name: Token,
+    },
+    Index {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+    },
+    Slice {
+        object: Box<Expr>,
+        bracket: Token,
+        start: Option<Box<Expr>>,
+        end: Option<Box<Expr>>,
+    },
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
+pub struct Param {
+    pub name: Token,
+    pub default: Option<Expr>,
+    pub is_rest: bool,
+}
+
+#[derive(Clone, Debug)]
 pub enum Statement {
-    Expr { expr: Expr },
-    Print { expr: Expr },
-    Var { name: Token, expr: Expr },
-    Block { statements: Vec<Statement> },
+    Expr {
+        expr: Expr,
+    },
+    Print {
+        keyword: Token,
+        expr: Expr,
+    },
+    Var {
+        name: Token,
+        expr: Expr,
+    },
+    Block {
+        brace: Token,
+        statements: Vec<Statement>,
+    },
+    If {
+        keyword: Token,
+        condition: Expr,
+        then_branch: Box<Statement>,
+        else_branch: Option<Box<Statement>>,
+    },
+    While {
+        keyword: Token,
+        condition: Expr,
+        body: Box<Statement>,
+    },
+    For {
+        keyword: Token,
+        initializer: Option<Box<Statement>>,
+        condition: Option<Expr>,
+        increment: Option<Expr>,
+        body: Box<Statement>,
+    },
+    Break {
+        keyword: Token,
+    },
+    Continue {
+        keyword: Token,
+    },
+    Function {
+        name: Token,
+        params: Vec<Param>,
+        body: Vec<Statement>,
+    },
+    Return {
+        keyword: Token,
+        value: Option<Expr>,
+    },
+    Throw {
+        keyword: Token,
+        value: Expr,
+    },
+    Try {
+        body: Vec<Statement>,
+        catch_clause: Option<(Token, Vec<Statement>)>,
+        finally_body: Option<Vec<Statement>>,
+    },
+    // `module` is a String token holding a path or an Identifier naming a module.
+    Import {
+        keyword: Token,
+        module: Token,
+        alias: Option<Token>,
+    },
+    FromImport {
+        keyword: Token,
+        module: Token,
+        names: Vec<Token>,
+    },
 }
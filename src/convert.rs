@@ -0,0 +1,187 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::rc::Rc;
+
+use crate::common::LiteralValue;
+use crate::error::RuntimeError;
+
+#[derive(Debug, Clone)]
+pub struct ConversionError {
+    pub expected: &'static str,
+    pub found: &'static str,
+    pub argument: Option<usize>,
+}
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.argument {
+            Some(index) => write!(
+                f,
+                "Expected {} for argument {}, found {}.",
+                self.expected,
+                index + 1,
+                self.found
+            ),
+            None => write!(f, "Expected {}, found {}.", self.expected, self.found),
+        }
+    }
+}
+impl std::error::Error for ConversionError {}
+impl From<ConversionError> for RuntimeError {
+    fn from(error: ConversionError) -> Self {
+        RuntimeError::new(error.to_string(), 0)
+    }
+}
+
+pub trait FromValue: Sized {
+    fn from_value(value: &LiteralValue) -> Result<Self, ConversionError>;
+}
+
+fn mismatch(expected: &'static str, value: &LiteralValue) -> ConversionError {
+    ConversionError {
+        expected,
+        found: value.type_name(),
+        argument: None,
+    }
+}
+
+impl FromValue for LiteralValue {
+    fn from_value(value: &LiteralValue) -> Result<Self, ConversionError> {
+        Ok(value.clone())
+    }
+}
+impl FromValue for bool {
+    fn from_value(value: &LiteralValue) -> Result<Self, ConversionError> {
+        match value {
+            LiteralValue::Bool(value) => Ok(*value),
+            _ => Err(mismatch("bool", value)),
+        }
+    }
+}
+impl FromValue for i64 {
+    fn from_value(value: &LiteralValue) -> Result<Self, ConversionError> {
+        match value {
+            LiteralValue::Integer(value) => Ok(*value),
+            LiteralValue::Float(float) if float.fract() == 0.0 => Ok(*float as i64),
+            _ => Err(mismatch("int", value)),
+        }
+    }
+}
+impl FromValue for f64 {
+    fn from_value(value: &LiteralValue) -> Result<Self, ConversionError> {
+        match value {
+            LiteralValue::Float(value) => Ok(*value),
+            LiteralValue::Integer(value) => Ok(*value as f64),
+            _ => Err(mismatch("float", value)),
+        }
+    }
+}
+impl FromValue for String {
+    fn from_value(value: &LiteralValue) -> Result<Self, ConversionError> {
+        match value {
+            LiteralValue::String(value) => Ok(value.clone()),
+            _ => Err(mismatch("string", value)),
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: &LiteralValue) -> Result<Self, ConversionError> {
+        match value {
+            LiteralValue::None => Ok(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: &LiteralValue) -> Result<Self, ConversionError> {
+        match value {
+            LiteralValue::List(items) => items.borrow().iter().map(T::from_value).collect(),
+            _ => Err(mismatch("list", value)),
+        }
+    }
+}
+impl<T: FromValue> FromValue for HashMap<String, T> {
+    fn from_value(value: &LiteralValue) -> Result<Self, ConversionError> {
+        match value {
+            LiteralValue::Map(entries) => entries
+                .borrow()
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), T::from_value(value)?)))
+                .collect(),
+            _ => Err(mismatch("map", value)),
+        }
+    }
+}
+
+pub trait IntoValue {
+    fn into_value(self) -> LiteralValue;
+}
+
+impl IntoValue for LiteralValue {
+    fn into_value(self) -> LiteralValue {
+        self
+    }
+}
+impl IntoValue for () {
+    fn into_value(self) -> LiteralValue {
+        LiteralValue::None
+    }
+}
+impl IntoValue for bool {
+    fn into_value(self) -> LiteralValue {
+        LiteralValue::Bool(self)
+    }
+}
+impl IntoValue for i64 {
+    fn into_value(self) -> LiteralValue {
+        LiteralValue::Integer(self)
+    }
+}
+impl IntoValue for f64 {
+    fn into_value(self) -> LiteralValue {
+        LiteralValue::Float(self)
+    }
+}
+impl IntoValue for String {
+    fn into_value(self) -> LiteralValue {
+        LiteralValue::String(self)
+    }
+}
+impl IntoValue for &str {
+    fn into_value(self) -> LiteralValue {
+        LiteralValue::String(self.to_string())
+    }
+}
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> LiteralValue {
+        match self {
+            Some(value) => value.into_value(),
+            None => LiteralValue::None,
+        }
+    }
+}
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> LiteralValue {
+        let items = self.into_iter().map(IntoValue::into_value).collect();
+        LiteralValue::List(Rc::new(RefCell::new(items)))
+    }
+}
+impl<T: IntoValue> IntoValue for HashMap<String, T> {
+    fn into_value(self) -> LiteralValue {
+        let entries: BTreeMap<String, LiteralValue> = self
+            .into_iter()
+            .map(|(key, value)| (key, value.into_value()))
+            .collect();
+        LiteralValue::Map(Rc::new(RefCell::new(entries)))
+    }
+}
+
+// Converts the argument at `index`, naming its position in the error.
+pub fn arg<T: FromValue>(args: &[LiteralValue], index: usize) -> Result<T, ConversionError> {
+    let value = args.get(index).unwrap_or(&LiteralValue::None);
+    T::from_value(value).map_err(|error| ConversionError {
+        argument: Some(index),
+        ..error
+    })
+}
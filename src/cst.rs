@@ -0,0 +1,512 @@
+use std::fmt::{self, Display};
+
+use crate::common::{Expr, Param, Statement, Token, TokenType};
+use crate::error::Error;
+use crate::parser::Parser;
+use crate::scanner::Scanner;
+
+// A lossless syntax tree: every token of the source, trivia included, grouped
+// under nodes that follow the AST. Printing the root gives back the source
+// byte for byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyntaxKind {
+    Program,
+
+    ExprStatement,
+    PrintStatement,
+    VarStatement,
+    Block,
+    IfStatement,
+    WhileStatement,
+    ForStatement,
+    BreakStatement,
+    ContinueStatement,
+    FunctionStatement,
+    ReturnStatement,
+    ThrowStatement,
+    TryStatement,
+    CatchClause,
+    FinallyClause,
+    ImportStatement,
+    FromImportStatement,
+
+    BinaryExpr,
+    GroupingExpr,
+    LiteralExpr,
+    UnaryExpr,
+    VarExpr,
+    AssignExpr,
+    LogicalExpr,
+    CallExpr,
+    LambdaExpr,
+    GetExpr,
+    IndexExpr,
+    SliceExpr,
+
+    ArgumentList,
+    KeywordArgument,
+    ParamList,
+    Param,
+}
+
+#[derive(Clone, Debug)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(Token),
+}
+
+#[derive(Clone, Debug)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode {
+    pub fn tokens(&self) -> Vec<&Token> {
+        let mut tokens = Vec::new();
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+}
+
+impl Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for token in self.tokens() {
+            for trivia in &token.leading_trivia {
+                write!(f, "{}", trivia.text)?;
+            }
+            write!(f, "{}", token.lexeme)?;
+            for trivia in &token.trailing_trivia {
+                write!(f, "{}", trivia.text)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// The root is a Program node whose last child is the Eof token, which holds
+// any trivia at the end of the file.
+pub fn parse(source: &str) -> Result<SyntaxNode, Error> {
+    let tokens = Scanner::with_trivia(source.to_string())
+        .scan_tokens()
+        .map_err(Error::Scan)?;
+    let code = tokens.iter().map(|token| Token {
+        leading_trivia: Box::new([]),
+        trailing_trivia: Box::new([]),
+        ..token.clone()
+    });
+    let statements = Parser::new(code.collect()).parse().map_err(Error::Parse)?;
+    let mut builder = Builder {
+        tokens: tokens.into_iter().peekable(),
+        stack: Vec::new(),
+    };
+    builder.start(SyntaxKind::Program);
+    for statement in &statements {
+        builder.statement(statement);
+    }
+    builder.token(TokenType::Eof);
+    Ok(builder.stack.pop().unwrap())
+}
+
+// Walks the AST and the token stream side by side, as the formatter does,
+// opening a node for each AST node and moving its tokens into it.
+struct Builder {
+    tokens: std::iter::Peekable<std::vec::IntoIter<Token>>,
+    stack: Vec<SyntaxNode>,
+}
+
+impl Builder {
+    fn start(&mut self, kind: SyntaxKind) {
+        self.stack.push(SyntaxNode {
+            kind,
+            children: Vec::new(),
+        });
+    }
+
+    fn finish(&mut self) {
+        let node = self.stack.pop().unwrap();
+        let parent = self.stack.last_mut().unwrap();
+        parent.children.push(SyntaxElement::Node(node));
+    }
+
+    fn peek(&mut self) -> &TokenType {
+        &self.tokens.peek().unwrap().token_type
+    }
+
+    fn token(&mut self, token_type: TokenType) {
+        let token = self.tokens.next().unwrap();
+        debug_assert_eq!(token.token_type, token_type);
+        let node = self.stack.last_mut().unwrap();
+        node.children.push(SyntaxElement::Token(token));
+    }
+
+    fn block(&mut self, statements: &[Statement]) {
+        self.start(SyntaxKind::Block);
+        self.token(TokenType::LeftBrace);
+        for statement in statements {
+            self.statement(statement);
+        }
+        self.token(TokenType::RightBrace);
+        self.finish();
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Expr { expr } => {
+                self.start(SyntaxKind::ExprStatement);
+                self.expr(expr);
+                self.token(TokenType::Semicolon);
+            }
+            Statement::Print { expr, .. } => {
+                self.start(SyntaxKind::PrintStatement);
+                self.token(TokenType::Print);
+                self.expr(expr);
+                self.token(TokenType::Semicolon);
+            }
+            Statement::Var { expr, .. } => {
+                self.start(SyntaxKind::VarStatement);
+                self.token(TokenType::Var);
+                self.token(TokenType::Identifier);
+                // `var x;` parses with a nil initializer that isn't in the source.
+                if self.peek() == &TokenType::Equal {
+                    self.token(TokenType::Equal);
+                    self.expr(expr);
+                }
+                self.token(TokenType::Semicolon);
+            }
+            Statement::Block { statements, .. } => return self.block(statements),
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.start(SyntaxKind::IfStatement);
+                self.token(TokenType::If);
+                self.token(TokenType::LeftParen);
+                self.expr(condition);
+                self.token(TokenType::RightParen);
+                self.statement(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.token(TokenType::Else);
+                    self.statement(else_branch);
+                }
+            }
+            Statement::While {
+                condition, body, ..
+            } => {
+                self.start(SyntaxKind::WhileStatement);
+                self.token(TokenType::While);
+                self.token(TokenType::LeftParen);
+                self.expr(condition);
+                self.token(TokenType::RightParen);
+                self.statement(body);
+            }
+            Statement::For {
+                initializer,
+                condition,
+                increment,
+                body,
+                ..
+            } => {
+                self.start(SyntaxKind::ForStatement);
+                self.token(TokenType::For);
+                self.token(TokenType::LeftParen);
+                match initializer {
+                    Some(initializer) => self.statement(initializer),
+                    None => self.token(TokenType::Semicolon),
+                }
+                if let Some(condition) = condition {
+                    self.expr(condition);
+                }
+                self.token(TokenType::Semicolon);
+                if let Some(increment) = increment {
+                    self.expr(increment);
+                }
+                self.token(TokenType::RightParen);
+                self.statement(body);
+            }
+            Statement::Break { .. } => {
+                self.start(SyntaxKind::BreakStatement);
+                self.token(TokenType::Break);
+                self.token(TokenType::Semicolon);
+            }
+            Statement::Continue { .. } => {
+                self.start(SyntaxKind::ContinueStatement);
+                self.token(TokenType::Continue);
+                self.token(TokenType::Semicolon);
+            }
+            Statement::Function { params, body, .. } => {
+                self.start(SyntaxKind::FunctionStatement);
+                self.token(TokenType::Fun);
+                self.token(TokenType::Identifier);
+                self.params(params, TokenType::LeftParen, TokenType::RightParen);
+                self.block(body);
+            }
+            Statement::Return { value, .. } => {
+                self.start(SyntaxKind::ReturnStatement);
+                self.token(TokenType::Return);
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+                self.token(TokenType::Semicolon);
+            }
+            Statement::Throw { value, .. } => {
+                self.start(SyntaxKind::ThrowStatement);
+                self.token(TokenType::Throw);
+                self.expr(value);
+                self.token(TokenType::Semicolon);
+            }
+            Statement::Try {
+                body,
+                catch_clause,
+                finally_body,
+            } => {
+                self.start(SyntaxKind::TryStatement);
+                self.token(TokenType::Try);
+                self.block(body);
+                if let Some((_, body)) = catch_clause {
+                    self.start(SyntaxKind::CatchClause);
+                    self.token(TokenType::Catch);
+                    self.token(TokenType::LeftParen);
+                    self.token(TokenType::Identifier);
+                    self.token(TokenType::RightParen);
+                    self.block(body);
+                    self.finish();
+                }
+                if let Some(body) = finally_body {
+                    self.start(SyntaxKind::FinallyClause);
+                    self.token(TokenType::Finally);
+                    self.block(body);
+                    self.finish();
+                }
+            }
+            Statement::Import { module, alias, .. } => {
+                self.start(SyntaxKind::ImportStatement);
+                self.token(TokenType::Import);
+                self.token(module.token_type.clone());
+                if alias.is_some() {
+                    self.token(TokenType::As);
+                    self.token(TokenType::Identifier);
+                }
+                self.token(TokenType::Semicolon);
+            }
+            Statement::FromImport { module, names, .. } => {
+                self.start(SyntaxKind::FromImportStatement);
+                self.token(TokenType::From);
+                self.token(module.token_type.clone());
+                self.token(TokenType::Import);
+                for i in 0..names.len() {
+                    if i > 0 {
+                        self.token(TokenType::Comma);
+                    }
+                    self.token(TokenType::Identifier);
+                }
+                self.token(TokenType::Semicolon);
+            }
+        }
+        self.finish();
+    }
+
+    fn params(&mut self, params: &[Param], open: TokenType, close: TokenType) {
+        self.start(SyntaxKind::ParamList);
+        self.token(open);
+        for (i, param) in params.iter().enumerate() {
+            if i > 0 {
+                self.token(TokenType::Comma);
+            }
+            self.start(SyntaxKind::Param);
+            if param.is_rest {
+                self.token(TokenType::Ellipsis);
+            }
+            self.token(TokenType::Identifier);
+            if let Some(default) = &param.default {
+                self.token(TokenType::Equal);
+                self.expr(default);
+            }
+            self.finish();
+        }
+        self.token(close);
+        self.finish();
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            }
+            | Expr::Logical {
+                left,
+                operator,
+                right,
+            } => {
+                self.start(match expr {
+                    Expr::Binary { .. } => SyntaxKind::BinaryExpr,
+                    _ => SyntaxKind::LogicalExpr,
+                });
+                self.expr(left);
+                self.token(operator.token_type.clone());
+                self.expr(right);
+            }
+            Expr::Grouping { expression } => {
+                self.start(SyntaxKind::GroupingExpr);
+                self.token(TokenType::LeftParen);
+                self.expr(expression);
+                self.token(TokenType::RightParen);
+            }
+            // Numbers, strings, true, false and nil are all a single token.
+            Expr::Literal { .. } => {
+                self.start(SyntaxKind::LiteralExpr);
+                let token_type = self.peek().clone();
+                self.token(token_type);
+            }
+            Expr::Unary { operator, right } => {
+                self.start(SyntaxKind::UnaryExpr);
+                self.token(operator.token_type.clone());
+                self.expr(right);
+            }
+            Expr::Var { .. } => {
+                self.start(SyntaxKind::VarExpr);
+                self.token(TokenType::Identifier);
+            }
+            Expr::Assign { value, .. } => {
+                self.start(SyntaxKind::AssignExpr);
+                self.token(TokenType::Identifier);
+                self.token(TokenType::Equal);
+                self.expr(value);
+            }
+            Expr::Call {
+                callee,
+                arguments,
+                keyword_arguments,
+                ..
+            } => {
+                self.start(SyntaxKind::CallExpr);
+                self.expr(callee);
+                self.start(SyntaxKind::ArgumentList);
+                self.token(TokenType::LeftParen);
+                for (i, argument) in arguments.iter().enumerate() {
+                    if i > 0 {
+                        self.token(TokenType::Comma);
+                    }
+                    self.expr(argument);
+                }
+                for (i, (_, value)) in keyword_arguments.iter().enumerate() {
+                    if i > 0 || !arguments.is_empty() {
+                        self.token(TokenType::Comma);
+                    }
+                    self.start(SyntaxKind::KeywordArgument);
+                    self.token(TokenType::Identifier);
+                    self.token(TokenType::Colon);
+                    self.expr(value);
+                    self.finish();
+                }
+                self.token(TokenType::RightParen);
+                self.finish();
+            }
+            Expr::Lambda { params, body } => {
+                self.start(SyntaxKind::LambdaExpr);
+                match (self.peek(), body.as_slice()) {
+                    // `|x| x + 1` parses to a lambda whose body returns the expression.
+                    (
+                        TokenType::BitwiseOr,
+                        [Statement::Return {
+                            value: Some(value), ..
+                        }],
+                    ) => {
+                        self.params(params, TokenType::BitwiseOr, TokenType::BitwiseOr);
+                        self.expr(value);
+                    }
+                    _ => {
+                        self.token(TokenType::Fun);
+                        self.params(params, TokenType::LeftParen, TokenType::RightParen);
+                        self.block(body);
+                    }
+                }
+            }
+            Expr::Get { object, .. } => {
+                self.start(SyntaxKind::GetExpr);
+                self.expr(object);
+                self.token(TokenType::Dot);
+                self.token(TokenType::Identifier);
+            }
+            Expr::Index { object, index, .. } => {
+                self.start(SyntaxKind::IndexExpr);
+                self.expr(object);
+                self.token(TokenType::LeftBracket);
+                self.expr(index);
+                self.token(TokenType::RightBracket);
+            }
+            Expr::Slice {
+                object, start, end, ..
+            } => {
+                self.start(SyntaxKind::SliceExpr);
+                self.expr(object);
+                self.token(TokenType::LeftBracket);
+                if let Some(start) = start {
+                    self.expr(start);
+                }
+                self.token(TokenType::Colon);
+                if let Some(end) = end {
+                    self.expr(end);
+                }
+                self.token(TokenType::RightBracket);
+            }
+        }
+        self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(source: &str) -> SyntaxNode {
+        let root = parse(source).unwrap();
+        assert_eq!(root.to_string(), source);
+        root
+    }
+
+    #[test]
+    fn round_trips_crlf_line_endings() {
+        round_trip("var a = 1;\r\n\r\nprint a; // done\r\nprint \"x\r\ny\";\r\n");
+    }
+
+    #[test]
+    fn round_trips_block_comments() {
+        round_trip("/* head\n   more */ var a = /* mid */ 1; /* tail */\nprint a;/**/\n");
+    }
+
+    #[test]
+    fn keeps_trivia_at_end_of_file_on_eof() {
+        let root = round_trip("print 1;\n\n// last\n  \n");
+        let eof = root.tokens().pop().unwrap();
+        assert_eq!(eof.token_type, TokenType::Eof);
+        let text: String = eof.leading_trivia.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(text, "\n\n// last\n  \n");
+    }
+
+    #[test]
+    fn round_trips_short_lambdas() {
+        let root = round_trip("var id = |a| a;\nprint id( 1 );\n");
+        let var = root.nodes().next().unwrap();
+        let lambda = var.nodes().next().unwrap();
+        assert_eq!(lambda.kind, SyntaxKind::LambdaExpr);
+        let kinds: Vec<SyntaxKind> = lambda.nodes().map(|node| node.kind).collect();
+        assert_eq!(kinds, [SyntaxKind::ParamList, SyntaxKind::VarExpr]);
+    }
+}
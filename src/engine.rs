@@ -0,0 +1,58 @@
+use std::io::Write;
+
+use crate::common::LiteralValue;
+use crate::error::Error;
+use crate::interpreter::Interpreter;
+use crate::parser::Parser;
+use crate::report::Reporter;
+use crate::scanner::Scanner;
+use crate::stdlib::Capabilities;
+
+pub type Value = LiteralValue;
+
+#[derive(Default)]
+pub struct Engine {
+    interpreter: Interpreter,
+    reporter: Option<Box<dyn Reporter>>,
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Engine::default()
+    }
+    pub fn with_capabilities(capabilities: Capabilities) -> Self {
+        Engine {
+            interpreter: Interpreter::with_capabilities(capabilities),
+            reporter: None,
+        }
+    }
+    pub fn interpreter(&mut self) -> &mut Interpreter {
+        &mut self.interpreter
+    }
+    pub fn set_output<W: Write + 'static>(&mut self, output: W) {
+        self.interpreter.set_output(output);
+    }
+    // Errors are always returned from `eval`; a reporter additionally sees
+    // each one as it happens. Engines have none by default.
+    pub fn set_reporter<R: Reporter + 'static>(&mut self, reporter: R) {
+        self.reporter = Some(Box::new(reporter));
+    }
+    // Runs `source` against the engine's global environment and returns the
+    // value of its trailing expression statement, or nil.
+    pub fn eval(&mut self, source: &str) -> Result<Value, Error> {
+        let result = self.run(source);
+        if let (Err(error), Some(reporter)) = (&result, &mut self.reporter) {
+            if !matches!(error, Error::Exit(_)) {
+                reporter.report(error);
+            }
+        }
+        result
+    }
+    fn run(&mut self, source: &str) -> Result<Value, Error> {
+        let tokens = Scanner::new(source.to_string())
+            .scan_tokens()
+            .map_err(Error::Scan)?;
+        let statements = Parser::new(tokens).parse().map_err(Error::Parse)?;
+        Ok(self.interpreter.interpret(&statements)?)
+    }
+}
@@ -1,25 +1,19 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::default::Default;
+use std::rc::Rc;
 
 use crate::common::{LiteralValue, Token};
 use crate::error::RuntimeError;
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Environment {
     var_map: HashMap<String, LiteralValue>,
-    enclosing: Option<Box<Environment>>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
 }
 
-impl Default for Environment {
-    fn default() -> Self {
-        Environment {
-            var_map: HashMap::new(),
-            enclosing: None,
-        }
-    }
-}
 impl Environment {
-    pub fn new(enclosing: Option<Box<Environment>>) -> Self {
+    pub fn new(enclosing: Option<Rc<RefCell<Environment>>>) -> Self {
         Environment {
             var_map: HashMap::new(),
             enclosing,
@@ -28,31 +22,59 @@ impl Environment {
     pub fn define(&mut self, name: Token, value: LiteralValue) {
         self.var_map.insert(name.lexeme, value);
     }
+    pub fn insert(&mut self, name: &str, value: LiteralValue) {
+        self.var_map.insert(name.to_string(), value);
+    }
+    // Bindings made directly in this scope, not in enclosing ones.
+    pub fn bindings(&self) -> &HashMap<String, LiteralValue> {
+        &self.var_map
+    }
+    // Every binding visible from this scope; inner bindings shadow outer ones.
+    pub fn visible_bindings(&self) -> HashMap<String, LiteralValue> {
+        let mut bindings = match &self.enclosing {
+            Some(enclosing) => enclosing.borrow().visible_bindings(),
+            None => HashMap::new(),
+        };
+        bindings.extend(
+            self.var_map
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone())),
+        );
+        bindings
+    }
+    pub fn lookup(&self, name: &str) -> Option<LiteralValue> {
+        match self.var_map.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self
+                .enclosing
+                .as_ref()
+                .and_then(|enclosing| enclosing.borrow().lookup(name)),
+        }
+    }
     pub fn get(&self, name: Token) -> Result<LiteralValue, RuntimeError> {
         if let Some(value) = self.var_map.get(&name.lexeme) {
             Ok(value.clone())
         } else if let Some(enclosing) = &self.enclosing {
-            enclosing.get(name)
+            enclosing.borrow().get(name)
         } else {
-            Err(RuntimeError {
-                message: format!("Undefined variable '{}'.", name.lexeme),
-            })
+            Err(RuntimeError::new(
+                format!("Undefined variable '{}'.", name.lexeme),
+                name.line,
+            ))
         }
     }
     pub fn assign(&mut self, name: Token, value: LiteralValue) -> Result<(), RuntimeError> {
-        if self.var_map.contains_key(&name.lexeme) {
-            self.var_map.insert(name.lexeme, value);
+        if let Some(slot) = self.var_map.get_mut(&name.lexeme) {
+            *slot = value;
             Ok(())
         } else if let Some(enclosing) = &self.enclosing {
-            let cloned_enclosing = enclosing.clone();
-            let mut mut_env = *cloned_enclosing;
-            let _ = &mut_env.assign(name, value);
-            Ok(())
+            enclosing.borrow_mut().assign(name, value)
         } else {
             let name_lexeme = name.lexeme;
-            Err(RuntimeError {
-                message: format!("Undefined variable '{name_lexeme}'."),
-            })
+            Err(RuntimeError::new(
+                format!("Undefined variable '{name_lexeme}'."),
+                name.line,
+            ))
         }
     }
 }
@@ -1,24 +1,168 @@
-use crate::common::Token;
+use crate::common::{LiteralValue, Token, TokenType};
 use std::fmt;
+use std::rc::Rc;
+
+#[derive(Debug, Clone)]
+pub struct ScanError {
+    pub line: usize,
+    pub message: String,
+}
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[line {}] Error: {}", self.line, self.message)
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct ParserError {
-    pub token: Token,
+    pub token: Box<Token>,
     pub message: String,
 }
 impl fmt::Display for ParserError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.message)
+        if self.token.token_type == TokenType::Eof {
+            write!(
+                f,
+                "[line {}] Error at end: {}",
+                self.token.line, self.message
+            )
+        } else {
+            write!(
+                f,
+                "[line {}] Error at '{}': {}",
+                self.token.line, self.token.lexeme, self.message
+            )
+        }
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub function: String,
+    pub line: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct RuntimeError {
     pub message: String,
+    pub line: usize,
+    // The value given to `throw`, if the error was raised by the script.
+    pub value: Option<LiteralValue>,
+    pub trace: Vec<Frame>,
+    // Set when the script called `exit()`; the error then unwinds to the host.
+    pub exit_code: Option<i32>,
+}
+impl RuntimeError {
+    pub fn new(message: String, line: usize) -> Self {
+        RuntimeError {
+            message,
+            line,
+            value: None,
+            trace: Vec::new(),
+            exit_code: None,
+        }
+    }
+    pub fn exit(code: i32) -> Self {
+        RuntimeError {
+            exit_code: Some(code),
+            ..RuntimeError::new(format!("Exited with code {}.", code), 0)
+        }
+    }
+    pub fn thrown(value: LiteralValue, line: usize) -> Self {
+        match value {
+            LiteralValue::Error(error) => (*error).clone(),
+            value => RuntimeError {
+                message: value.to_string(),
+                line,
+                value: Some(value),
+                trace: Vec::new(),
+                exit_code: None,
+            },
+        }
+    }
+    pub fn into_value(self) -> LiteralValue {
+        match self.value {
+            Some(value) => value,
+            None => LiteralValue::Error(Rc::new(self)),
+        }
+    }
 }
-impl fmt::Display for RuntimeError{
+impl fmt::Display for RuntimeError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.message)
+        // Each frame records where its function was called from, so the line
+        // shown for a frame is the call site of the next one, or the error.
+        let mut entries = vec![(
+            "<script>",
+            self.trace.first().map_or(self.line, |frame| frame.line),
+        )];
+        for (index, frame) in self.trace.iter().enumerate() {
+            let line = self
+                .trace
+                .get(index + 1)
+                .map_or(self.line, |next| next.line);
+            entries.push((frame.function.as_str(), line));
+        }
+
+        writeln!(f, "Traceback (most recent call last):")?;
+        let mut index = 0;
+        while index < entries.len() {
+            let (function, line) = entries[index];
+            writeln!(f, "  line {}, in {}", line, function)?;
+            let repeated = entries[index + 1..]
+                .iter()
+                .take_while(|entry| **entry == entries[index])
+                .count();
+            if repeated > 0 {
+                writeln!(f, "  [Previous line repeated {} more times]", repeated)?;
+            }
+            index += repeated + 1;
+        }
+        write!(f, "Error: {}", self.message)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Unwind {
+    Break,
+    Continue,
+    Return(LiteralValue),
+    Error(RuntimeError),
+}
+impl From<RuntimeError> for Unwind {
+    fn from(error: RuntimeError) -> Self {
+        Unwind::Error(error)
     }
 }
 
+#[derive(Debug, Clone)]
+pub enum Error {
+    Scan(Vec<ScanError>),
+    Parse(Vec<ParserError>),
+    Runtime(RuntimeError),
+    Exit(i32),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Scan(errors) => {
+                let lines: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+                write!(f, "{}", lines.join("\n"))
+            }
+            Error::Parse(errors) => {
+                let lines: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+                write!(f, "{}", lines.join("\n"))
+            }
+            Error::Runtime(error) => write!(f, "{}", error),
+            Error::Exit(code) => write!(f, "Exited with code {}.", code),
+        }
+    }
+}
+impl std::error::Error for Error {}
+impl From<RuntimeError> for Error {
+    fn from(error: RuntimeError) -> Self {
+        match error.exit_code {
+            Some(code) => Error::Exit(code),
+            None => Error::Runtime(error),
+        }
+    }
+}
@@ -0,0 +1,671 @@
+use crate::common::{Expr, Param, Statement, Token, TokenType};
+use crate::error::Error;
+use crate::parser::Parser;
+use crate::scanner::{self, Comment, Scanner};
+
+const INDENT: &str = "    ";
+// Argument and parameter lists that would run past this column go one item
+// per line.
+const WIDTH: usize = 100;
+
+// Layout comes from the syntax tree, but the text of every token comes from the
+// source: the formatter walks the tree and the trivia-keeping token stream side
+// by side, so literals keep their spelling and comments keep their place.
+pub fn format(source: &str) -> Result<String, Error> {
+    let tokens = Scanner::with_trivia(source.to_string())
+        .scan_tokens()
+        .map_err(Error::Scan)?;
+    let statements = Parser::new(tokens.clone()).parse().map_err(Error::Parse)?;
+    let mut formatter = Formatter::new(&tokens);
+    for statement in &statements {
+        formatter.line();
+        formatter.statement(statement);
+    }
+    formatter.finish();
+    Ok(formatter.out)
+}
+
+struct Formatter<'a> {
+    tokens: &'a [Token],
+    current: usize,
+    comments: Vec<Comment<'a>>,
+    next_comment: usize,
+    out: String,
+    depth: usize,
+    // Source line of the last token or comment written, to tell trailing comments
+    // from comments on their own line and to keep blank lines between statements.
+    last_line: usize,
+    // Comments that go at the end of the current output line.
+    trailing: Vec<String>,
+    // Set right after a `{`, where blank lines are dropped.
+    block_start: bool,
+    // Above zero while a list is being laid out on one line to measure it;
+    // lists inside it stay on one line too.
+    flat: usize,
+}
+
+// Everything `list` needs to take back a layout that didn't fit.
+struct Snapshot {
+    current: usize,
+    next_comment: usize,
+    out: usize,
+    last_line: usize,
+    trailing: Vec<String>,
+    block_start: bool,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(tokens: &'a [Token]) -> Self {
+        Formatter {
+            tokens,
+            current: 0,
+            comments: scanner::comments(tokens),
+            next_comment: 0,
+            out: String::new(),
+            depth: 0,
+            last_line: 0,
+            trailing: Vec::new(),
+            block_start: false,
+            flat: 0,
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            current: self.current,
+            next_comment: self.next_comment,
+            out: self.out.len(),
+            last_line: self.last_line,
+            trailing: self.trailing.clone(),
+            block_start: self.block_start,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.current = snapshot.current;
+        self.next_comment = snapshot.next_comment;
+        self.out.truncate(snapshot.out);
+        self.last_line = snapshot.last_line;
+        self.trailing = snapshot.trailing;
+        self.block_start = snapshot.block_start;
+    }
+
+    // Whether every output line since `start` fits in WIDTH.
+    fn fits(&self, start: usize) -> bool {
+        let line_start = self.out[..start].rfind('\n').map_or(0, |i| i + 1);
+        self.out[line_start..]
+            .lines()
+            .all(|line| line.chars().count() <= WIDTH)
+    }
+
+    fn column(&self) -> usize {
+        let line_start = self.out.rfind('\n').map_or(0, |i| i + 1);
+        self.out[line_start..].chars().count()
+    }
+
+    // Writes `count` items, separated by commas, and then the closing
+    // bracket. They stay on one line if it fits up to the bracket; otherwise
+    // each item gets an indented line of its own and the bracket goes on the
+    // next. Breaking can't help once the opening bracket is past WIDTH.
+    fn list(&mut self, count: usize, close: TokenType, item: impl Fn(&mut Self, usize)) {
+        let snapshot = self.snapshot();
+        let breakable = self.flat == 0 && count > 0 && self.column() < WIDTH;
+        self.flat += 1;
+        self.items(count, &item);
+        self.flat -= 1;
+        self.token(close.clone());
+        if !breakable || self.fits(snapshot.out) {
+            return;
+        }
+        self.restore(snapshot);
+        self.depth += 1;
+        for i in 0..count {
+            self.comment_lines();
+            self.new_line(None);
+            item(self, i);
+            if i + 1 < count {
+                self.token(TokenType::Comma);
+            }
+        }
+        self.comment_lines();
+        self.depth -= 1;
+        self.new_line(None);
+        self.token(close);
+    }
+
+    fn items(&mut self, count: usize, item: &impl Fn(&mut Self, usize)) {
+        for i in 0..count {
+            if i > 0 {
+                self.token(TokenType::Comma);
+                self.write(" ");
+            }
+            item(self, i);
+        }
+    }
+
+    fn peek(&self) -> &TokenType {
+        &self.tokens[self.current].token_type
+    }
+
+    // Takes the comments before the next token that haven't been written yet.
+    fn comments(&mut self) -> Vec<Comment<'a>> {
+        let mut comments = Vec::new();
+        while let Some(&comment) = self.comments.get(self.next_comment) {
+            if comment.token != self.current {
+                break;
+            }
+            comments.push(comment);
+            self.next_comment += 1;
+        }
+        comments
+    }
+
+    fn write(&mut self, text: &str) {
+        self.out.push_str(text);
+    }
+
+    // Writes the next source token, which the tree says must be a `token_type`.
+    // Comments in front of it can't stay inside the statement, so they move to
+    // the end of the line.
+    fn token(&mut self, token_type: TokenType) {
+        for comment in self.comments() {
+            self.trailing.push(comment.text.to_string());
+            self.last_line = comment.end_line();
+        }
+        let token = &self.tokens[self.current];
+        debug_assert_eq!(token.token_type, token_type);
+        self.current += 1;
+        self.out.push_str(&token.lexeme);
+        self.last_line = token.line;
+    }
+
+    // Writes the comments before the next token. A comment on the same line as
+    // the code before it stays there; the others get lines of their own.
+    // Returns whether any comment got its own line.
+    fn comment_lines(&mut self) -> bool {
+        let mut wrote_line = false;
+        for comment in self.comments() {
+            if comment.line == self.last_line && !self.out.is_empty() {
+                self.trailing.push(comment.text.to_string());
+            } else {
+                self.new_line(Some(comment.line));
+                self.out.push_str(comment.text);
+                wrote_line = true;
+            }
+            self.last_line = comment.end_line();
+        }
+        wrote_line
+    }
+
+    fn finish_line(&mut self) {
+        for comment in std::mem::take(&mut self.trailing) {
+            self.out.push(' ');
+            self.out.push_str(&comment);
+        }
+        self.out.push('\n');
+    }
+
+    // Ends the current line and indents the next one. With `next_line`, one
+    // blank line is kept if the source had any before that line.
+    fn new_line(&mut self, next_line: Option<usize>) {
+        if !self.out.is_empty() {
+            self.finish_line();
+            if next_line.is_some_and(|line| line > self.last_line + 1) && !self.block_start {
+                self.out.push('\n');
+            }
+        }
+        self.block_start = false;
+        self.out.push_str(&INDENT.repeat(self.depth));
+    }
+
+    // Starts the line for the next statement.
+    fn line(&mut self) {
+        self.comment_lines();
+        let next = start_line(&self.tokens[self.current]);
+        self.new_line(Some(next));
+    }
+
+    fn finish(&mut self) {
+        self.comment_lines();
+        if !self.out.is_empty() {
+            self.finish_line();
+        }
+    }
+
+    fn block(&mut self, statements: &[Statement]) {
+        self.token(TokenType::LeftBrace);
+        self.depth += 1;
+        self.block_start = true;
+        for statement in statements {
+            self.line();
+            self.statement(statement);
+        }
+        let wrote_comments = self.comment_lines();
+        self.depth -= 1;
+        if !statements.is_empty() || wrote_comments {
+            self.new_line(None);
+        }
+        self.block_start = false;
+        self.token(TokenType::RightBrace);
+    }
+
+    // The body of an if, else or loop: blocks stay on the same line, single
+    // statements get an indented line of their own.
+    fn body(&mut self, body: &Statement) {
+        match body {
+            Statement::Block { statements, .. } => {
+                self.write(" ");
+                self.block(statements);
+            }
+            _ => {
+                self.depth += 1;
+                self.line();
+                self.statement(body);
+                self.depth -= 1;
+            }
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Expr { expr } => {
+                self.expr(expr);
+                self.token(TokenType::Semicolon);
+            }
+            Statement::Print { expr, .. } => {
+                self.token(TokenType::Print);
+                self.write(" ");
+                self.expr(expr);
+                self.token(TokenType::Semicolon);
+            }
+            Statement::Var { expr, .. } => {
+                self.token(TokenType::Var);
+                self.write(" ");
+                self.token(TokenType::Identifier);
+                // `var x;` parses with a nil initializer that isn't in the source.
+                if self.peek() == &TokenType::Equal {
+                    self.write(" ");
+                    self.token(TokenType::Equal);
+                    self.write(" ");
+                    self.expr(expr);
+                }
+                self.token(TokenType::Semicolon);
+            }
+            Statement::Block { statements, .. } => self.block(statements),
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.token(TokenType::If);
+                self.write(" ");
+                self.token(TokenType::LeftParen);
+                self.expr(condition);
+                self.token(TokenType::RightParen);
+                self.body(then_branch);
+                if let Some(else_branch) = else_branch {
+                    match **then_branch {
+                        Statement::Block { .. } => self.write(" "),
+                        _ => self.line(),
+                    }
+                    self.token(TokenType::Else);
+                    match **else_branch {
+                        Statement::If { .. } => {
+                            self.write(" ");
+                            self.statement(else_branch);
+                        }
+                        _ => self.body(else_branch),
+                    }
+                }
+            }
+            Statement::While {
+                condition, body, ..
+            } => {
+                self.token(TokenType::While);
+                self.write(" ");
+                self.token(TokenType::LeftParen);
+                self.expr(condition);
+                self.token(TokenType::RightParen);
+                self.body(body);
+            }
+            Statement::For {
+                initializer,
+                condition,
+                increment,
+                body,
+                ..
+            } => {
+                self.token(TokenType::For);
+                self.write(" ");
+                self.token(TokenType::LeftParen);
+                match initializer {
+                    Some(initializer) => self.statement(initializer),
+                    None => self.token(TokenType::Semicolon),
+                }
+                if let Some(condition) = condition {
+                    self.write(" ");
+                    self.expr(condition);
+                }
+                self.token(TokenType::Semicolon);
+                if let Some(increment) = increment {
+                    self.write(" ");
+                    self.expr(increment);
+                }
+                self.token(TokenType::RightParen);
+                self.body(body);
+            }
+            Statement::Break { .. } => {
+                self.token(TokenType::Break);
+                self.token(TokenType::Semicolon);
+            }
+            Statement::Continue { .. } => {
+                self.token(TokenType::Continue);
+                self.token(TokenType::Semicolon);
+            }
+            Statement::Function { params, body, .. } => {
+                self.token(TokenType::Fun);
+                self.write(" ");
+                self.token(TokenType::Identifier);
+                self.function(params, body);
+            }
+            Statement::Return { value, .. } => {
+                self.token(TokenType::Return);
+                if let Some(value) = value {
+                    self.write(" ");
+                    self.expr(value);
+                }
+                self.token(TokenType::Semicolon);
+            }
+            Statement::Throw { value, .. } => {
+                self.token(TokenType::Throw);
+                self.write(" ");
+                self.expr(value);
+                self.token(TokenType::Semicolon);
+            }
+            Statement::Try {
+                body,
+                catch_clause,
+                finally_body,
+            } => {
+                self.token(TokenType::Try);
+                self.write(" ");
+                self.block(body);
+                if let Some((_, body)) = catch_clause {
+                    self.write(" ");
+                    self.token(TokenType::Catch);
+                    self.write(" ");
+                    self.token(TokenType::LeftParen);
+                    self.token(TokenType::Identifier);
+                    self.token(TokenType::RightParen);
+                    self.write(" ");
+                    self.block(body);
+                }
+                if let Some(body) = finally_body {
+                    self.write(" ");
+                    self.token(TokenType::Finally);
+                    self.write(" ");
+                    self.block(body);
+                }
+            }
+            Statement::Import { module, alias, .. } => {
+                self.token(TokenType::Import);
+                self.write(" ");
+                self.token(module.token_type.clone());
+                if alias.is_some() {
+                    self.write(" ");
+                    self.token(TokenType::As);
+                    self.write(" ");
+                    self.token(TokenType::Identifier);
+                }
+                self.token(TokenType::Semicolon);
+            }
+            Statement::FromImport { module, names, .. } => {
+                self.token(TokenType::From);
+                self.write(" ");
+                self.token(module.token_type.clone());
+                self.write(" ");
+                self.token(TokenType::Import);
+                self.write(" ");
+                for i in 0..names.len() {
+                    if i > 0 {
+                        self.token(TokenType::Comma);
+                        self.write(" ");
+                    }
+                    self.token(TokenType::Identifier);
+                }
+                self.token(TokenType::Semicolon);
+            }
+        }
+    }
+
+    fn function(&mut self, params: &[Param], body: &[Statement]) {
+        self.token(TokenType::LeftParen);
+        self.list(params.len(), TokenType::RightParen, |formatter, i| {
+            formatter.param(&params[i])
+        });
+        self.write(" ");
+        self.block(body);
+    }
+
+    fn param(&mut self, param: &Param) {
+        if param.is_rest {
+            self.token(TokenType::Ellipsis);
+        }
+        self.token(TokenType::Identifier);
+        if let Some(default) = &param.default {
+            self.write(" ");
+            self.token(TokenType::Equal);
+            self.write(" ");
+            self.expr(default);
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            }
+            | Expr::Logical {
+                left,
+                operator,
+                right,
+            } => {
+                self.expr(left);
+                self.write(" ");
+                self.token(operator.token_type.clone());
+                self.write(" ");
+                self.expr(right);
+            }
+            Expr::Grouping { expression } => {
+                self.token(TokenType::LeftParen);
+                self.expr(expression);
+                self.token(TokenType::RightParen);
+            }
+            // Numbers, strings, true, false and nil are all a single token.
+            Expr::Literal { .. } => self.token(self.peek().clone()),
+            Expr::Unary { operator, right } => {
+                self.token(operator.token_type.clone());
+                self.expr(right);
+            }
+            Expr::Var { .. } => self.token(TokenType::Identifier),
+            Expr::Assign { value, .. } => {
+                self.token(TokenType::Identifier);
+                self.write(" ");
+                self.token(TokenType::Equal);
+                self.write(" ");
+                self.expr(value);
+            }
+            Expr::Call {
+                callee,
+                arguments,
+                keyword_arguments,
+                ..
+            } => {
+                self.expr(callee);
+                self.token(TokenType::LeftParen);
+                let count = arguments.len() + keyword_arguments.len();
+                self.list(
+                    count,
+                    TokenType::RightParen,
+                    |formatter, i| match arguments.get(i) {
+                        Some(argument) => formatter.expr(argument),
+                        None => {
+                            let (_, value) = &keyword_arguments[i - arguments.len()];
+                            formatter.token(TokenType::Identifier);
+                            formatter.token(TokenType::Colon);
+                            formatter.write(" ");
+                            formatter.expr(value);
+                        }
+                    },
+                );
+            }
+            Expr::Lambda { params, body } => match (self.peek(), body.as_slice()) {
+                // `|x| x + 1` parses to a lambda whose body returns the expression.
+                (
+                    TokenType::BitwiseOr,
+                    [Statement::Return {
+                        value: Some(value), ..
+                    }],
+                ) => {
+                    self.token(TokenType::BitwiseOr);
+                    self.items(params.len(), &|formatter: &mut Self, i| {
+                        formatter.param(&params[i])
+                    });
+                    self.token(TokenType::BitwiseOr);
+                    self.write(" ");
+                    self.expr(value);
+                }
+                _ => {
+                    self.token(TokenType::Fun);
+                    self.write(" ");
+                    self.function(params, body);
+                }
+            },
+            Expr::Get { object, .. } => {
+                self.expr(object);
+                self.token(TokenType::Dot);
+                self.token(TokenType::Identifier);
+            }
+            Expr::Index { object, index, .. } => {
+                self.expr(object);
+                self.token(TokenType::LeftBracket);
+                self.expr(index);
+                self.token(TokenType::RightBracket);
+            }
+            Expr::Slice {
+                object, start, end, ..
+            } => {
+                self.expr(object);
+                self.token(TokenType::LeftBracket);
+                if let Some(start) = start {
+                    self.expr(start);
+                }
+                self.token(TokenType::Colon);
+                if let Some(end) = end {
+                    self.expr(end);
+                }
+                self.token(TokenType::RightBracket);
+            }
+        }
+    }
+}
+
+// Tokens carry the line they end on.
+fn start_line(token: &Token) -> usize {
+    match token.token_type {
+        TokenType::String => token.line - token.lexeme.matches('\n').count(),
+        _ => token.line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_formats(source: &str, expected: &str) {
+        let formatted = format(source).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(format(&formatted).unwrap(), formatted, "not idempotent");
+    }
+
+    #[test]
+    fn formatting_is_idempotent() {
+        let sources = [
+            "var a=1;print a+2;\n\n\n\nif(a){print a;}else print -a;\n",
+            "// head\nfun f(x,y=2,...rest){ // open\n  return |z| x+z; /* tail */\n}\n",
+            "try{throw \"x\";}catch(e){print e;}finally{}\nfor(var i=0;i<3;i=i+1)print i;\n",
+            "print configure(\"example.com\", 8080, \"administrator\", \"correct horse\", timeout: 60, x: 1);\n",
+            "var s = \"multi\nline\"; /* block\n comment */\nprint s[1:2]; // end\n",
+        ];
+        for source in sources {
+            let formatted = format(source).unwrap();
+            assert_eq!(format(&formatted).unwrap(), formatted, "{}", source);
+        }
+    }
+
+    #[test]
+    fn comments_keep_their_lines() {
+        assert_formats(
+            "// first\n\n\n/* block\n   comment */\nvar a = 1;   // trailing\n{\n\n  // inside\n  print a;\n  // last\n}\n// end\n",
+            "// first\n\n/* block\n   comment */\nvar a = 1; // trailing\n{\n    // inside\n    print a;\n    // last\n}\n// end\n",
+        );
+    }
+
+    #[test]
+    fn comments_inside_a_statement_move_to_the_end_of_its_line() {
+        assert_formats(
+            "print f(1, /* arg */ 2);\nvar b = // why\n  3;\n",
+            "print f(1, 2); /* arg */\nvar b = 3; // why\n",
+        );
+    }
+
+    #[test]
+    fn short_lists_stay_on_one_line() {
+        assert_formats(
+            "fun f(a,b){return g(a,\n  b);}\n",
+            "fun f(a, b) {\n    return g(a, b);\n}\n",
+        );
+    }
+
+    #[test]
+    fn long_argument_lists_break_one_per_line() {
+        assert_formats(
+            "print configure(\"example.com\", 8080, \"administrator\", \"correct horse battery staple\", timeout: 60, retries: 10);\n",
+            "print configure(\n    \"example.com\",\n    8080,\n    \"administrator\",\n    \"correct horse battery staple\",\n    timeout: 60,\n    retries: 10\n);\n",
+        );
+    }
+
+    #[test]
+    fn comments_in_broken_lists_stay_with_their_items() {
+        assert_formats(
+            "print configure( // open\n  \"example.com\", 8080, // port\n  // own line\n  \"administrator\", \"correct horse battery staple\", timeout: 60, retries: 10 // last\n);\n",
+            "print configure( // open\n    \"example.com\",\n    8080, // port\n    // own line\n    \"administrator\",\n    \"correct horse battery staple\",\n    timeout: 60,\n    retries: 10 // last\n);\n",
+        );
+    }
+
+    #[test]
+    fn long_parameter_lists_break_one_per_line() {
+        assert_formats(
+            "fun connect(hostname, port, username, password, timeout = 30, retries = 5, backoff = 2, ...extra_options) { return port; }\n",
+            "fun connect(\n    hostname,\n    port,\n    username,\n    password,\n    timeout = 30,\n    retries = 5,\n    backoff = 2,\n    ...extra_options\n) {\n    return port;\n}\n",
+        );
+    }
+
+    #[test]
+    fn inner_lists_stay_together_when_the_outer_one_breaks() {
+        assert_formats(
+            "print outer(first_argument_value, inner(second_argument_value, third_argument_value), fourth_argument_value);\n",
+            "print outer(\n    first_argument_value,\n    inner(second_argument_value, third_argument_value),\n    fourth_argument_value\n);\n",
+        );
+    }
+    #[test]
+    fn lists_that_start_past_the_width_stay_on_one_line() {
+        let source = format!("{}f(first, second);{}", "{".repeat(26), "}".repeat(26));
+        let formatted = format(&source).unwrap();
+        let line = format!("{}f(first, second);\n", INDENT.repeat(26));
+        assert!(formatted.contains(&line), "{}", formatted);
+    }
+}
@@ -0,0 +1,106 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::common::{LiteralValue, Param, Statement};
+use crate::environment::Environment;
+use crate::error::RuntimeError;
+
+pub struct Function {
+    pub name: Option<String>,
+    pub params: Vec<Param>,
+    pub body: Rc<Vec<Statement>>,
+    pub closure: Rc<RefCell<Environment>>,
+}
+
+impl Function {
+    pub fn new(
+        name: Option<String>,
+        params: Vec<Param>,
+        body: Rc<Vec<Statement>>,
+        closure: Rc<RefCell<Environment>>,
+    ) -> Self {
+        Function {
+            name,
+            params,
+            body,
+            closure,
+        }
+    }
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or("lambda")
+    }
+}
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "<fn {}>", name),
+            None => write!(f, "<fn lambda>"),
+        }
+    }
+}
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Arity {
+    Exact(usize),
+    Range(usize, usize),
+    AtLeast(usize),
+}
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match *self {
+            Arity::Exact(arity) => count == arity,
+            Arity::Range(min, max) => (min..=max).contains(&count),
+            Arity::AtLeast(min) => count >= min,
+        }
+    }
+}
+impl From<usize> for Arity {
+    fn from(arity: usize) -> Self {
+        Arity::Exact(arity)
+    }
+}
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Arity::Exact(arity) => write!(f, "{}", arity),
+            Arity::Range(min, max) => write!(f, "{} to {}", min, max),
+            Arity::AtLeast(min) => write!(f, "at least {}", min),
+        }
+    }
+}
+
+// Errors returned by a native function with line 0 are reported at the line
+// of the call that invoked it.
+pub type NativeFn = dyn Fn(&[LiteralValue]) -> Result<LiteralValue, RuntimeError>;
+
+pub struct NativeFunction {
+    pub name: String,
+    pub arity: Arity,
+    pub func: Rc<NativeFn>,
+}
+
+impl NativeFunction {
+    pub fn new(name: &str, arity: Arity, func: Rc<NativeFn>) -> Self {
+        NativeFunction {
+            name: name.to_string(),
+            arity,
+            func,
+        }
+    }
+}
+impl fmt::Display for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+impl fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
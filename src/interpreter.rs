@@ -1,68 +1,188 @@
-use crate::common::{Expr, LiteralValue, Statement, TokenType};
+use crate::common::{Expr, LiteralValue, Param, Statement, Token, TokenType};
+use crate::convert::{ConversionError, FromValue, IntoValue};
 use crate::environment::Environment;
-use crate::error::RuntimeError;
+use crate::error::{Error, Frame, RuntimeError, Unwind};
+use crate::function::{Arity, Function, NativeFunction};
+use crate::parser::Parser;
+use crate::scanner::Scanner;
+use crate::stdlib::{self, Capabilities};
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::default::Default;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+const DEFAULT_MAX_CALL_DEPTH: usize = 256;
+// Limits how deeply execute and evaluate may recurse, whatever the mix of
+// calls, blocks and subexpressions.
+const MAX_NESTING_DEPTH: usize = 10_000;
+// Each nesting level checks for this much native stack and moves onto a new
+// segment of STACK_SEGMENT bytes when it runs short, so deep scripts don't
+// overflow whichever thread the host runs them on.
+pub(crate) const RED_ZONE: usize = 256 * 1024;
+pub(crate) const STACK_SEGMENT: usize = 4 * 1024 * 1024;
+const MODULE_EXTENSION: &str = "ownpy";
 
 pub struct Interpreter {
-    had_error: bool,
-    had_runtime_error: bool,
-    environment: Environment,
+    // Natives and host-set values, shared by the script and every module.
+    builtins: Rc<RefCell<Environment>>,
+    // The script's own top-level scope; each module gets its own.
+    globals: Rc<RefCell<Environment>>,
+    environment: Rc<RefCell<Environment>>,
+    call_stack: Vec<Frame>,
+    max_call_depth: usize,
+    nesting_depth: usize,
+    output: Rc<RefCell<Box<dyn Write>>>,
+    script_path: Option<PathBuf>,
+    search_path: Vec<PathBuf>,
+    // Off in sandboxes: then no file can be found as a module.
+    imports: bool,
+    // Namespaces of modules that have finished loading, by canonical path.
+    modules: HashMap<PathBuf, LiteralValue>,
+    // Modules being loaded, innermost last, to detect circular imports.
+    loading: Vec<PathBuf>,
 }
 
 impl Default for Interpreter {
     fn default() -> Self {
-        Interpreter {
-            had_error: false,
-            had_runtime_error: false,
-            environment: Environment::default(),
-        }
+        Interpreter::with_capabilities(Capabilities::default())
     }
 }
 impl Interpreter {
-    fn is_equal(&self, left_value: &LiteralValue, right_value: &LiteralValue) -> bool {
-        if left_value == right_value {
-            return true;
+    pub fn with_capabilities(capabilities: Capabilities) -> Self {
+        let builtins = Rc::new(RefCell::new(Environment::default()));
+        let globals = Rc::new(RefCell::new(Environment::new(Some(Rc::clone(&builtins)))));
+        let mut interpreter = Interpreter {
+            builtins,
+            environment: Rc::clone(&globals),
+            globals,
+            call_stack: Vec::new(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            nesting_depth: 0,
+            output: Rc::new(RefCell::new(Box::new(io::stdout()))),
+            script_path: None,
+            search_path: Vec::new(),
+            imports: capabilities.imports,
+            modules: HashMap::new(),
+            loading: Vec::new(),
+        };
+        stdlib::register(&mut interpreter, capabilities);
+        interpreter
+    }
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.max_call_depth = max_call_depth;
+    }
+    // Program output from `print` goes here; stdout unless a host replaces it.
+    pub fn set_output<W: Write + 'static>(&mut self, output: W) {
+        *self.output.borrow_mut() = Box::new(output);
+    }
+    // Shared with natives such as `input()` that write to the program output.
+    pub(crate) fn output(&self) -> Rc<RefCell<Box<dyn Write>>> {
+        Rc::clone(&self.output)
+    }
+    pub fn register_fn<F>(&mut self, name: &str, arity: impl Into<Arity>, func: F)
+    where
+        F: Fn(&[LiteralValue]) -> Result<LiteralValue, RuntimeError> + 'static,
+    {
+        let function = NativeFunction::new(name, arity.into(), Rc::new(func));
+        self.set_global(name, LiteralValue::NativeFunction(Rc::new(function)));
+    }
+    // New globals are visible to every module; a global the script already
+    // defined is updated in place.
+    pub fn set_global<T: IntoValue>(&mut self, name: &str, value: T) {
+        let scope = match self.globals.borrow().bindings().contains_key(name) {
+            true => &self.globals,
+            false => &self.builtins,
+        };
+        scope.borrow_mut().insert(name, value.into_value());
+    }
+    // Returns `Ok(None)` when no global called `name` is defined.
+    pub fn get_global<T: FromValue>(&self, name: &str) -> Result<Option<T>, ConversionError> {
+        match self.globals.borrow().lookup(name) {
+            Some(value) => T::from_value(&value).map(Some),
+            None => Ok(None),
         }
-        return false;
     }
-    fn is_truthy(&self, literal_value: LiteralValue) -> bool {
-        match literal_value {
-            LiteralValue::None => false,
-            LiteralValue::Bool(value) => value,
-            LiteralValue::Integer(value) => {
-                if value == 0 {
-                    false
-                } else {
-                    true
-                }
-            }
-            LiteralValue::String(value) => {
-                if value == "" {
-                    false
-                } else {
-                    true
-                }
-            }
-            LiteralValue::Float(value) => {
-                if value == 0.0 {
-                    false
-                } else {
-                    true
-                }
-            }
+    // The script's own top-level bindings, sorted by name.
+    pub fn globals(&self) -> Vec<(String, LiteralValue)> {
+        let mut bindings: Vec<(String, LiteralValue)> = self
+            .globals
+            .borrow()
+            .bindings()
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        bindings.sort_by(|left, right| left.0.cmp(&right.0));
+        bindings
+    }
+    // Every name visible at the top level, including natives, sorted by name.
+    pub fn bindings(&self) -> Vec<(String, LiteralValue)> {
+        let mut bindings: Vec<(String, LiteralValue)> = self
+            .environment
+            .borrow()
+            .visible_bindings()
+            .into_iter()
+            .collect();
+        bindings.sort_by(|left, right| left.0.cmp(&right.0));
+        bindings
+    }
+    // The properties `value.name` can read, as `get_property` resolves them.
+    pub fn property_names(value: &LiteralValue) -> Vec<String> {
+        match value {
+            LiteralValue::Error(_) => vec!["line".to_string(), "message".to_string()],
+            LiteralValue::Map(entries) => entries.borrow().keys().cloned().collect(),
+            _ => stdlib::method_names(value)
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
         }
     }
+    // Forgets everything the script defined and every loaded module, keeping
+    // natives and globals set by the host.
+    pub fn reset(&mut self) {
+        self.globals = Rc::new(RefCell::new(Environment::new(Some(Rc::clone(
+            &self.builtins,
+        )))));
+        self.environment = Rc::clone(&self.globals);
+        self.modules.clear();
+    }
+    // Imports in the script resolve relative to this file's directory, or the
+    // working directory when unset.
+    pub fn set_script_path(&mut self, path: impl Into<PathBuf>) {
+        self.script_path = Some(path.into());
+    }
+    // Directories searched, in order, for imports not found relative to the importer.
+    pub fn add_search_path(&mut self, path: impl Into<PathBuf>) {
+        self.search_path.push(path.into());
+    }
+    fn is_equal(&self, left_value: &LiteralValue, right_value: &LiteralValue) -> bool {
+        left_value == right_value
+    }
     fn return_error_string(&mut self, operator: TokenType) -> String {
-        return format!("Invalid data type for {operator}");
+        format!("Invalid data type for {operator}")
     }
 
     fn evaluate_binary(
         &mut self,
         left_literal_val: &LiteralValue,
-        operator_token_type: &TokenType,
+        operator: &Token,
         right_literal_val: &LiteralValue,
     ) -> Result<LiteralValue, RuntimeError> {
-        match (left_literal_val, operator_token_type, right_literal_val) {
+        // Mixed integer and float operands are evaluated as floats.
+        match (left_literal_val, right_literal_val) {
+            (LiteralValue::Integer(left_value), LiteralValue::Float(_)) => {
+                let left_float = LiteralValue::Float(*left_value as f64);
+                return self.evaluate_binary(&left_float, operator, right_literal_val);
+            }
+            (LiteralValue::Float(_), LiteralValue::Integer(right_value)) => {
+                let right_float = LiteralValue::Float(*right_value as f64);
+                return self.evaluate_binary(left_literal_val, operator, &right_float);
+            }
+            _ => {}
+        }
+        match (left_literal_val, &operator.token_type, right_literal_val) {
             (
                 LiteralValue::Float(left_value),
                 TokenType::Minus,
@@ -72,7 +192,10 @@ impl Interpreter {
                 LiteralValue::Integer(left_value),
                 TokenType::Minus,
                 LiteralValue::Integer(right_value),
-            ) => Ok(LiteralValue::Integer(left_value - right_value)),
+            ) => left_value
+                .checked_sub(*right_value)
+                .map(LiteralValue::Integer)
+                .ok_or_else(|| RuntimeError::new("Integer overflow.".to_string(), operator.line)),
             (
                 LiteralValue::Float(left_value),
                 TokenType::Plus,
@@ -82,7 +205,10 @@ impl Interpreter {
                 LiteralValue::Integer(left_value),
                 TokenType::Plus,
                 LiteralValue::Integer(right_value),
-            ) => Ok(LiteralValue::Integer(left_value + right_value)),
+            ) => left_value
+                .checked_add(*right_value)
+                .map(LiteralValue::Integer)
+                .ok_or_else(|| RuntimeError::new("Integer overflow.".to_string(), operator.line)),
             (
                 LiteralValue::String(left_value),
                 TokenType::Plus,
@@ -100,17 +226,26 @@ impl Interpreter {
                 LiteralValue::Integer(left_value),
                 TokenType::Star,
                 LiteralValue::Integer(right_value),
-            ) => Ok(LiteralValue::Integer(left_value * right_value)),
+            ) => left_value
+                .checked_mul(*right_value)
+                .map(LiteralValue::Integer)
+                .ok_or_else(|| RuntimeError::new("Integer overflow.".to_string(), operator.line)),
             (
                 LiteralValue::Float(left_value),
                 TokenType::Slash,
                 LiteralValue::Float(right_value),
             ) => Ok(LiteralValue::Float(left_value / right_value)),
+            (LiteralValue::Integer(_), TokenType::Slash, LiteralValue::Integer(0)) => Err(
+                RuntimeError::new("Division by zero.".to_string(), operator.line),
+            ),
+            // Dividing integers is true division, as it was before integer literals existed.
             (
                 LiteralValue::Integer(left_value),
                 TokenType::Slash,
                 LiteralValue::Integer(right_value),
-            ) => Ok(LiteralValue::Integer(left_value / right_value)),
+            ) => Ok(LiteralValue::Float(
+                *left_value as f64 / *right_value as f64,
+            )),
             (
                 LiteralValue::Float(left_value),
                 TokenType::Greater,
@@ -158,88 +293,770 @@ impl Interpreter {
             (_, TokenType::EqualEqual, _) => Ok(LiteralValue::Bool(
                 self.is_equal(left_literal_val, right_literal_val),
             )),
-            _ => Err(RuntimeError {
-                message: "Invalid operator".to_owned(),
-            }),
+            _ => Err(RuntimeError::new(
+                format!(
+                    "Unsupported operand types for '{}': {} and {}.",
+                    operator.lexeme,
+                    left_literal_val.type_name(),
+                    right_literal_val.type_name()
+                ),
+                operator.line,
+            )),
         }
     }
     fn evaluate_unary(
         &mut self,
-        operator_token_type: &TokenType,
+        operator: &Token,
         right_literal_val: &LiteralValue,
     ) -> Result<LiteralValue, RuntimeError> {
-        match operator_token_type {
+        match operator.token_type {
             TokenType::Minus => match right_literal_val {
                 LiteralValue::Float(value) => Ok(LiteralValue::Float(-value)),
-                LiteralValue::Integer(value) => Ok(LiteralValue::Integer(-value)),
-                _ => Err(RuntimeError {
-                    message: self.return_error_string(TokenType::Minus),
-                }),
+                LiteralValue::Integer(value) => value
+                    .checked_neg()
+                    .map(LiteralValue::Integer)
+                    .ok_or_else(|| {
+                        RuntimeError::new("Integer overflow.".to_string(), operator.line)
+                    }),
+                _ => Err(RuntimeError::new(
+                    self.return_error_string(TokenType::Minus),
+                    operator.line,
+                )),
             },
-            TokenType::Bang => Ok(LiteralValue::Bool(
-                !self.is_truthy(right_literal_val.clone()),
-            )),
+            TokenType::Bang => Ok(LiteralValue::Bool(!right_literal_val.is_truthy())),
             _ => Ok(LiteralValue::None),
         }
     }
 
-    fn evaluate(&mut self, expr: Box<Expr>) -> Result<LiteralValue, RuntimeError> {
-        match *expr {
+    fn nested<T, E: From<RuntimeError>>(
+        &mut self,
+        run: impl FnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, E> {
+        if self.nesting_depth >= MAX_NESTING_DEPTH {
+            // Nodes don't all carry a line, so blame the innermost call.
+            let line = self.call_stack.last().map_or(0, |frame| frame.line);
+            let message = format!(
+                "Stack overflow: maximum nesting depth of {} exceeded.",
+                MAX_NESTING_DEPTH
+            );
+            return Err(RuntimeError::new(message, line).into());
+        }
+        self.nesting_depth += 1;
+        let result = stacker::maybe_grow(RED_ZONE, STACK_SEGMENT, || run(self));
+        self.nesting_depth -= 1;
+        result
+    }
+    fn evaluate(&mut self, expr: &Expr) -> Result<LiteralValue, RuntimeError> {
+        self.nested(|interpreter| interpreter.evaluate_expr(expr))
+    }
+    fn evaluate_expr(&mut self, expr: &Expr) -> Result<LiteralValue, RuntimeError> {
+        match expr {
             Expr::Binary {
                 left,
                 operator,
                 right,
             } => {
-                let left_literal_val = self.evaluate(left).unwrap();
-                let right_literal_val = self.evaluate(right).unwrap();
-                self.evaluate_binary(&left_literal_val, &operator.token_type, &right_literal_val)
+                let left_literal_val = self.evaluate(left)?;
+                let right_literal_val = self.evaluate(right)?;
+                self.evaluate_binary(&left_literal_val, operator, &right_literal_val)
             }
             Expr::Grouping { expression } => self.evaluate(expression),
-            Expr::Literal { value } => Ok(value),
+            Expr::Literal { value } => Ok(value.clone()),
             Expr::Unary { operator, right } => {
-                let right_literal_val: LiteralValue = self.evaluate(right).unwrap();
-                self.evaluate_unary(&operator.token_type, &right_literal_val)
+                let right_literal_val: LiteralValue = self.evaluate(right)?;
+                self.evaluate_unary(operator, &right_literal_val)
             }
-            Expr::Var { name } => self.environment.get(name),
+            Expr::Var { name } => self.environment.borrow().get(name.clone()),
             Expr::Assign { name, value } => {
                 let value = self.evaluate(value)?;
-                let _ = self.environment.assign(name, value.clone());
+                self.environment
+                    .borrow_mut()
+                    .assign(name.clone(), value.clone())?;
                 Ok(value)
             }
+            Expr::Logical {
+                left,
+                operator,
+                right,
+            } => {
+                let left_literal_val = self.evaluate(left)?;
+                let short_circuits = match operator.token_type {
+                    TokenType::Or => left_literal_val.is_truthy(),
+                    _ => !left_literal_val.is_truthy(),
+                };
+                if short_circuits {
+                    return Ok(left_literal_val);
+                }
+                self.evaluate(right)
+            }
+            Expr::Call {
+                callee,
+                paren,
+                arguments,
+                keyword_arguments,
+            } => {
+                let callee_val = self.evaluate(callee)?;
+                let mut argument_vals = Vec::new();
+                for argument in arguments {
+                    argument_vals.push(self.evaluate(argument)?);
+                }
+                let mut keyword_argument_vals = Vec::new();
+                for (keyword, argument) in keyword_arguments {
+                    keyword_argument_vals.push((keyword.clone(), self.evaluate(argument)?));
+                }
+                match callee_val {
+                    LiteralValue::Function(function) => {
+                        self.call_function(&function, paren, argument_vals, keyword_argument_vals)
+                    }
+                    LiteralValue::NativeFunction(function) => {
+                        self.call_native(&function, paren, argument_vals, keyword_argument_vals)
+                    }
+                    _ => Err(RuntimeError::new(
+                        "Can only call functions.".to_string(),
+                        paren.line,
+                    )),
+                }
+            }
+            Expr::Lambda { params, body } => Ok(LiteralValue::Function(Rc::new(Function::new(
+                None,
+                params.clone(),
+                Rc::new(body.clone()),
+                Rc::clone(&self.environment),
+            )))),
+            Expr::Get { object, name } => {
+                let object_val = self.evaluate(object)?;
+                self.get_property(&object_val, name)
+            }
+            Expr::Index {
+                object,
+                bracket,
+                index,
+            } => {
+                let object_val = self.evaluate(object)?;
+                let index_val = self.evaluate(index)?;
+                self.index(&object_val, &index_val, bracket)
+            }
+            Expr::Slice {
+                object,
+                bracket,
+                start,
+                end,
+            } => {
+                let object_val = self.evaluate(object)?;
+                let start = self.slice_bound(start, bracket)?;
+                let end = self.slice_bound(end, bracket)?;
+                self.slice(&object_val, start, end, bracket)
+            }
+        }
+    }
+    fn slice_bound(
+        &mut self,
+        bound: &Option<Box<Expr>>,
+        bracket: &Token,
+    ) -> Result<Option<i64>, RuntimeError> {
+        match bound {
+            Some(bound) => {
+                let value = self.evaluate(bound)?;
+                self.index_int(&value, bracket).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+    fn index_int(&self, index: &LiteralValue, bracket: &Token) -> Result<i64, RuntimeError> {
+        i64::from_value(index).map_err(|_| {
+            RuntimeError::new(
+                format!("Indices must be integers, found {}.", index.type_name()),
+                bracket.line,
+            )
+        })
+    }
+    // Strings are indexed by Unicode scalar value; negative indices count from the end.
+    fn index(
+        &self,
+        object: &LiteralValue,
+        index: &LiteralValue,
+        bracket: &Token,
+    ) -> Result<LiteralValue, RuntimeError> {
+        let position = self.index_int(index, bracket)?;
+        let (length, item) = match object {
+            LiteralValue::String(value) => {
+                let length = value.chars().count();
+                let item = resolve_index(position, length)
+                    .and_then(|index| value.chars().nth(index))
+                    .map(|c| LiteralValue::String(c.to_string()));
+                (length, item)
+            }
+            LiteralValue::List(items) => {
+                let items = items.borrow();
+                let item = resolve_index(position, items.len()).map(|index| items[index].clone());
+                (items.len(), item)
+            }
+            _ => {
+                return Err(RuntimeError::new(
+                    format!("'{}' value is not indexable.", object.type_name()),
+                    bracket.line,
+                ))
+            }
+        };
+        item.ok_or_else(|| {
+            RuntimeError::new(
+                format!(
+                    "Index {} out of range for {} of length {}.",
+                    position,
+                    object.type_name(),
+                    length
+                ),
+                bracket.line,
+            )
+        })
+    }
+    fn slice(
+        &self,
+        object: &LiteralValue,
+        start: Option<i64>,
+        end: Option<i64>,
+        bracket: &Token,
+    ) -> Result<LiteralValue, RuntimeError> {
+        match object {
+            LiteralValue::String(value) => {
+                let length = value.chars().count();
+                let start = clamp_bound(start, 0, length);
+                let end = clamp_bound(end, length, length);
+                let sliced = value
+                    .chars()
+                    .skip(start)
+                    .take(end.saturating_sub(start))
+                    .collect();
+                Ok(LiteralValue::String(sliced))
+            }
+            LiteralValue::List(items) => {
+                let items = items.borrow();
+                let start = clamp_bound(start, 0, items.len());
+                let end = clamp_bound(end, items.len(), items.len()).max(start);
+                Ok(LiteralValue::List(Rc::new(RefCell::new(
+                    items[start..end].to_vec(),
+                ))))
+            }
+            _ => Err(RuntimeError::new(
+                format!("'{}' value is not sliceable.", object.type_name()),
+                bracket.line,
+            )),
+        }
+    }
+    fn get_property(
+        &self,
+        object: &LiteralValue,
+        name: &Token,
+    ) -> Result<LiteralValue, RuntimeError> {
+        match (object, name.lexeme.as_str()) {
+            (LiteralValue::Error(error), "message") => {
+                Ok(LiteralValue::String(error.message.clone()))
+            }
+            (LiteralValue::Error(error), "line") => Ok(LiteralValue::Integer(error.line as i64)),
+            (LiteralValue::Map(entries), key) if entries.borrow().contains_key(key) => {
+                Ok(entries.borrow()[key].clone())
+            }
+            // Strings and numbers have built-in methods bound to the value.
+            (object, key) => stdlib::method(object, key).ok_or_else(|| {
+                RuntimeError::new(
+                    format!(
+                        "'{}' value has no property '{}'.",
+                        object.type_name(),
+                        name.lexeme
+                    ),
+                    name.line,
+                )
+            }),
+        }
+    }
+    fn call_function(
+        &mut self,
+        function: &Function,
+        paren: &Token,
+        arguments: Vec<LiteralValue>,
+        keyword_arguments: Vec<(Token, LiteralValue)>,
+    ) -> Result<LiteralValue, RuntimeError> {
+        let environment = Rc::new(RefCell::new(Environment::new(Some(Rc::clone(
+            &function.closure,
+        )))));
+        // Defaults are evaluated inside the call's environment so they can
+        // refer to the parameters bound before them.
+        let previous = std::mem::replace(&mut self.environment, Rc::clone(&environment));
+        let bound = self.bind_arguments(function, paren, arguments, keyword_arguments);
+        self.environment = previous;
+        bound?;
+        if self.call_stack.len() >= self.max_call_depth {
+            return Err(RuntimeError::new(
+                format!(
+                    "Stack overflow: maximum call depth of {} exceeded.",
+                    self.max_call_depth
+                ),
+                paren.line,
+            ));
+        }
+        self.call_stack.push(Frame {
+            function: function.display_name().to_string(),
+            line: paren.line,
+        });
+        let result = match self.execute_block(&function.body, environment) {
+            Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Error(mut error)) => {
+                // The innermost call an error leaves is where it was raised,
+                // so the stack at that point is the one worth reporting.
+                if error.trace.is_empty() {
+                    error.trace = self.call_stack.clone();
+                }
+                Err(error)
+            }
+            _ => Ok(LiteralValue::None),
+        };
+        self.call_stack.pop();
+        result
+    }
+    fn call_native(
+        &mut self,
+        function: &NativeFunction,
+        paren: &Token,
+        arguments: Vec<LiteralValue>,
+        keyword_arguments: Vec<(Token, LiteralValue)>,
+    ) -> Result<LiteralValue, RuntimeError> {
+        let name = &function.name;
+        if let Some((keyword, _)) = keyword_arguments.first() {
+            return Err(RuntimeError::new(
+                format!(
+                    "{name}() got an unexpected keyword argument '{}'.",
+                    keyword.lexeme
+                ),
+                paren.line,
+            ));
+        }
+        if !function.arity.accepts(arguments.len()) {
+            return Err(RuntimeError::new(
+                format!(
+                    "{name}() takes {} arguments but {} were given.",
+                    function.arity,
+                    arguments.len()
+                ),
+                paren.line,
+            ));
         }
+        (function.func)(&arguments).map_err(|mut error| {
+            if error.line == 0 {
+                error.line = paren.line;
+                error.message = format!("{name}(): {}", error.message);
+            }
+            error
+        })
     }
-    fn execute(&mut self, stmt: Statement) {
+    fn bind_arguments(
+        &mut self,
+        function: &Function,
+        paren: &Token,
+        arguments: Vec<LiteralValue>,
+        keyword_arguments: Vec<(Token, LiteralValue)>,
+    ) -> Result<(), RuntimeError> {
+        let name = function.display_name();
+        let call_error = |message: String| RuntimeError::new(message, paren.line);
+        let rest_param: Option<&Param> = function.params.last().filter(|param| param.is_rest);
+        let positional_params = match rest_param {
+            Some(_) => &function.params[..function.params.len() - 1],
+            None => &function.params[..],
+        };
+
+        let mut slots: Vec<Option<LiteralValue>> = vec![None; positional_params.len()];
+        let mut extra_arguments = Vec::new();
+        let argument_count = arguments.len();
+        for (index, argument) in arguments.into_iter().enumerate() {
+            if index < slots.len() {
+                slots[index] = Some(argument);
+            } else if rest_param.is_some() {
+                extra_arguments.push(argument);
+            } else {
+                return Err(call_error(format!(
+                    "{name}() takes {} positional arguments but {argument_count} were given.",
+                    slots.len()
+                )));
+            }
+        }
+        for (keyword, argument) in keyword_arguments {
+            let lexeme = &keyword.lexeme;
+            match positional_params
+                .iter()
+                .position(|param| &param.name.lexeme == lexeme)
+            {
+                Some(index) if slots[index].is_some() => {
+                    return Err(call_error(format!(
+                        "{name}() got multiple values for parameter '{lexeme}'."
+                    )));
+                }
+                Some(index) => slots[index] = Some(argument),
+                None => {
+                    return Err(call_error(format!(
+                        "{name}() got an unexpected keyword argument '{lexeme}'."
+                    )));
+                }
+            }
+        }
+
+        for (param, slot) in positional_params.iter().zip(slots) {
+            let value = match (slot, &param.default) {
+                (Some(value), _) => value,
+                (None, Some(default)) => self.evaluate(default)?,
+                (None, None) => {
+                    return Err(call_error(format!(
+                        "{name}() missing argument for parameter '{}'.",
+                        param.name.lexeme
+                    )));
+                }
+            };
+            self.environment
+                .borrow_mut()
+                .define(param.name.clone(), value);
+        }
+        if let Some(rest_param) = rest_param {
+            self.environment.borrow_mut().define(
+                rest_param.name.clone(),
+                LiteralValue::List(Rc::new(RefCell::new(extra_arguments))),
+            );
+        }
+        Ok(())
+    }
+    fn execute(&mut self, stmt: &Statement) -> Result<(), Unwind> {
+        self.nested(|interpreter| interpreter.execute_statement(stmt))
+    }
+    fn execute_statement(&mut self, stmt: &Statement) -> Result<(), Unwind> {
         match stmt {
-            Statement::Print { expr } => {
-                let val = self.evaluate(Box::new(expr)).unwrap();
-                println!("{}", val);
+            Statement::Print { keyword, expr } => {
+                let val = self.evaluate(expr)?;
+                if let Err(error) = writeln!(self.output.borrow_mut(), "{}", val) {
+                    return Err(RuntimeError::new(
+                        format!("Failed to write output: {}", error),
+                        keyword.line,
+                    )
+                    .into());
+                }
             }
             Statement::Expr { expr } => {
-                self.evaluate(Box::new(expr)).unwrap();
+                self.evaluate(expr)?;
             }
             Statement::Var { name, expr } => {
-                if let Expr::Literal {
-                    value: LiteralValue::None,
-                } = expr
-                {
-                    return;
+                let val: LiteralValue = self.evaluate(expr)?;
+                self.environment.borrow_mut().define(name.clone(), val);
+            }
+            Statement::Block { statements, .. } => {
+                let environment = Environment::new(Some(Rc::clone(&self.environment)));
+                self.execute_block(statements, Rc::new(RefCell::new(environment)))?;
+            }
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let condition_val = self.evaluate(condition)?;
+                if condition_val.is_truthy() {
+                    self.execute(then_branch)?;
+                } else if let Some(else_branch) = else_branch {
+                    self.execute(else_branch)?;
+                }
+            }
+            Statement::While {
+                condition, body, ..
+            } => {
+                while {
+                    let condition_val = self.evaluate(condition)?;
+                    condition_val.is_truthy()
+                } {
+                    match self.execute(body) {
+                        Ok(()) | Err(Unwind::Continue) => {}
+                        Err(Unwind::Break) => break,
+                        Err(unwind) => return Err(unwind),
+                    }
+                }
+            }
+            Statement::For {
+                initializer,
+                condition,
+                increment,
+                body,
+                ..
+            } => {
+                let previous = Rc::clone(&self.environment);
+                self.environment =
+                    Rc::new(RefCell::new(Environment::new(Some(Rc::clone(&previous)))));
+                let result = self.execute_for(initializer, condition, increment, body);
+                self.environment = previous;
+                result?;
+            }
+            Statement::Break { .. } => return Err(Unwind::Break),
+            Statement::Continue { .. } => return Err(Unwind::Continue),
+            Statement::Function { name, params, body } => {
+                let function = Function::new(
+                    Some(name.lexeme.clone()),
+                    params.clone(),
+                    Rc::new(body.clone()),
+                    Rc::clone(&self.environment),
+                );
+                self.environment
+                    .borrow_mut()
+                    .define(name.clone(), LiteralValue::Function(Rc::new(function)));
+            }
+            Statement::Return { value, .. } => {
+                let return_val = match value {
+                    Some(value) => self.evaluate(value)?,
+                    None => LiteralValue::None,
+                };
+                return Err(Unwind::Return(return_val));
+            }
+            Statement::Throw { keyword, value } => {
+                let thrown_val = self.evaluate(value)?;
+                return Err(RuntimeError::thrown(thrown_val, keyword.line).into());
+            }
+            Statement::Try {
+                body,
+                catch_clause,
+                finally_body,
+            } => {
+                let environment = Environment::new(Some(Rc::clone(&self.environment)));
+                let result = match (
+                    self.execute_block(body, Rc::new(RefCell::new(environment))),
+                    catch_clause,
+                ) {
+                    // `exit()` unwinds as an error that scripts can't catch.
+                    (Err(Unwind::Error(error)), Some((name, catch_body)))
+                        if error.exit_code.is_none() =>
+                    {
+                        let mut environment = Environment::new(Some(Rc::clone(&self.environment)));
+                        environment.define(name.clone(), error.into_value());
+                        self.execute_block(catch_body, Rc::new(RefCell::new(environment)))
+                    }
+                    (result, _) => result,
+                };
+                if let Some(finally_body) = finally_body {
+                    let environment = Environment::new(Some(Rc::clone(&self.environment)));
+                    self.execute_block(finally_body, Rc::new(RefCell::new(environment)))?;
+                }
+                result?;
+            }
+            Statement::Import { module, alias, .. } => {
+                let namespace = self.import_module(module)?;
+                let name = match alias {
+                    Some(alias) => alias.lexeme.clone(),
+                    None => module_binding(module),
+                };
+                self.environment.borrow_mut().insert(&name, namespace);
+            }
+            Statement::FromImport { module, names, .. } => {
+                let namespace = self.import_module(module)?;
+                let LiteralValue::Map(entries) = namespace else {
+                    unreachable!("module namespaces are maps")
+                };
+                for name in names {
+                    let value = entries.borrow().get(&name.lexeme).cloned().ok_or_else(|| {
+                        RuntimeError::new(
+                            format!(
+                                "Module '{}' has no member '{}'.",
+                                module_spec(module),
+                                name.lexeme
+                            ),
+                            name.line,
+                        )
+                    })?;
+                    self.environment.borrow_mut().define(name.clone(), value);
                 }
-                let val: LiteralValue = self.evaluate(Box::new(expr)).unwrap();
-                self.environment.define(name, val);
             }
-            Statement::Block { statements } => self.execute_block(statements),
         }
+        Ok(())
     }
-    fn execute_block(&mut self, stmts: Vec<Statement>) {
-        let previous = self.environment.clone();
-        for stmt in stmts {
-            self.execute(stmt);
+    // `import utils` and `import "lib/utils"` both load lib/utils.ownpy-style
+    // paths, first relative to the importing file, then from the search path.
+    fn resolve_module(&self, module: &Token) -> Result<PathBuf, RuntimeError> {
+        let spec = module_spec(module);
+        let not_found = || RuntimeError::new(format!("No module named '{}'.", spec), module.line);
+        if !self.imports {
+            return Err(not_found());
         }
-        self.environment = previous
+        let mut file = PathBuf::from(&spec);
+        if file.extension().is_none() {
+            file.set_extension(MODULE_EXTENSION);
+        }
+        let importer = self.loading.last().or(self.script_path.as_ref());
+        let base = importer
+            .and_then(|path| path.parent())
+            .unwrap_or(Path::new(""));
+        let mut candidates = vec![base.join(&file)];
+        candidates.extend(self.search_path.iter().map(|dir| dir.join(&file)));
+        candidates
+            .into_iter()
+            .find(|candidate| candidate.is_file())
+            .and_then(|candidate| candidate.canonicalize().ok())
+            .ok_or_else(not_found)
     }
-    pub fn interpret(&mut self, stmts: Vec<Statement>) {
+    // Runs a module once in its own global scope and returns its namespace: a
+    // map of its top-level bindings. Later imports reuse the cached namespace.
+    fn import_module(&mut self, module: &Token) -> Result<LiteralValue, RuntimeError> {
+        let path = self.resolve_module(module)?;
+        if let Some(namespace) = self.modules.get(&path) {
+            return Ok(namespace.clone());
+        }
+        let spec = module_spec(module);
+        if let Some(start) = self.loading.iter().position(|loading| *loading == path) {
+            let cycle: Vec<String> = self.loading[start..]
+                .iter()
+                .chain([&path])
+                .map(|path| {
+                    path.file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect();
+            return Err(RuntimeError::new(
+                format!("Circular import: {}.", cycle.join(" -> ")),
+                module.line,
+            ));
+        }
+        let source = fs::read_to_string(&path).map_err(|err| {
+            RuntimeError::new(
+                format!("Cannot read module '{}': {}.", spec, err),
+                module.line,
+            )
+        })?;
+        let statements = Scanner::new(source)
+            .scan_tokens()
+            .map_err(Error::Scan)
+            .and_then(|tokens| Parser::new(tokens).parse().map_err(Error::Parse))
+            .map_err(|error| {
+                RuntimeError::new(
+                    format!("Error in module '{}':\n{}", spec, error),
+                    module.line,
+                )
+            })?;
+
+        let environment = Rc::new(RefCell::new(Environment::new(Some(Rc::clone(
+            &self.builtins,
+        )))));
+        self.loading.push(path.clone());
+        self.call_stack.push(Frame {
+            function: format!("<module {}>", spec),
+            line: module.line,
+        });
+        // The parser rejects `break`, `continue` and `return` at the top level.
+        let result = match self.execute_block(&statements, Rc::clone(&environment)) {
+            Err(Unwind::Error(mut error)) => {
+                if error.trace.is_empty() {
+                    error.trace = self.call_stack.clone();
+                }
+                Err(error)
+            }
+            _ => Ok(()),
+        };
+        self.call_stack.pop();
+        self.loading.pop();
+        result?;
+
+        let entries = environment
+            .borrow()
+            .bindings()
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        let namespace = LiteralValue::Map(Rc::new(RefCell::new(entries)));
+        self.modules.insert(path, namespace.clone());
+        Ok(namespace)
+    }
+    fn execute_for(
+        &mut self,
+        initializer: &Option<Box<Statement>>,
+        condition: &Option<Expr>,
+        increment: &Option<Expr>,
+        body: &Statement,
+    ) -> Result<(), Unwind> {
+        if let Some(initializer) = initializer {
+            self.execute(initializer)?;
+        }
+        loop {
+            if let Some(condition) = condition {
+                let condition_val = self.evaluate(condition)?;
+                if !condition_val.is_truthy() {
+                    break;
+                }
+            }
+            match self.execute(body) {
+                Ok(()) | Err(Unwind::Continue) => {}
+                Err(Unwind::Break) => break,
+                Err(unwind) => return Err(unwind),
+            }
+            if let Some(increment) = increment {
+                self.evaluate(increment)?;
+            }
+        }
+        Ok(())
+    }
+    fn execute_block(
+        &mut self,
+        stmts: &[Statement],
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<(), Unwind> {
+        let previous = Rc::clone(&self.environment);
+        self.environment = environment;
+        let result = stmts.iter().try_for_each(|stmt| self.execute(stmt));
+        self.environment = previous;
+        result
+    }
+    pub fn interpret(&mut self, stmts: &[Statement]) -> Result<LiteralValue, RuntimeError> {
+        let mut last_value = LiteralValue::None;
         for s in stmts {
-            self.execute(s);
+            // A trailing expression statement gives the program its value.
+            let result = match s {
+                Statement::Expr { expr } => self.evaluate(expr).map(|value| last_value = value),
+                _ => {
+                    last_value = LiteralValue::None;
+                    // The parser rejects `break`, `continue` and `return` outside of
+                    // loops and functions, so only errors can reach the top level.
+                    match self.execute(s) {
+                        Err(Unwind::Error(error)) => Err(error),
+                        _ => Ok(()),
+                    }
+                }
+            };
+            result?;
         }
+        Ok(last_value)
+    }
+}
+
+fn resolve_index(position: i64, length: usize) -> Option<usize> {
+    let position = match position < 0 {
+        true => position + length as i64,
+        false => position,
+    };
+    usize::try_from(position)
+        .ok()
+        .filter(|position| *position < length)
+}
+
+// Slice bounds are clamped to the sequence, like Python's.
+fn clamp_bound(bound: Option<i64>, default: usize, length: usize) -> usize {
+    match bound {
+        None => default,
+        Some(bound) if bound < 0 => (bound + length as i64).max(0) as usize,
+        Some(bound) => (bound as usize).min(length),
     }
 }
+
+fn module_spec(module: &Token) -> String {
+    match &module.literal_value {
+        LiteralValue::String(path) => path.clone(),
+        _ => module.lexeme.clone(),
+    }
+}
+
+// `import "lib/utils"` binds the module as `utils`.
+pub(crate) fn module_binding(module: &Token) -> String {
+    let spec = module_spec(module);
+    Path::new(&spec)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or(spec)
+}
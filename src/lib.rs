@@ -0,0 +1,29 @@
+pub mod common;
+pub mod convert;
+pub mod cst;
+pub mod engine;
+pub mod environment;
+pub mod error;
+pub mod formatter;
+pub mod function;
+pub mod interpreter;
+pub mod lint;
+pub mod lsp;
+pub mod parser;
+pub mod printer;
+pub mod report;
+pub mod resolver;
+pub mod scanner;
+mod stdlib;
+mod utils;
+
+pub use common::{Expr, LiteralValue, Statement, Token, TokenType, Trivia, TriviaKind};
+pub use convert::{arg, ConversionError, FromValue, IntoValue};
+pub use engine::{Engine, Value};
+pub use error::{Error, RuntimeError};
+pub use function::Arity;
+pub use interpreter::Interpreter;
+pub use parser::Parser;
+pub use report::{Reporter, StderrReporter};
+pub use scanner::Scanner;
+pub use stdlib::Capabilities;
@@ -0,0 +1,628 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+
+use crate::common::{Expr, LiteralValue, Statement, Token, TokenType};
+use crate::error::Error;
+use crate::parser::Parser;
+use crate::resolver::{self, BindingKind};
+use crate::scanner::{self, Scanner};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Rule {
+    UnusedVariable,
+    ShadowedVariable,
+    UnreachableCode,
+    SelfAssignment,
+    MismatchedComparison,
+    ConstantCondition,
+    EmptyBlock,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 7] = [
+        Rule::UnusedVariable,
+        Rule::ShadowedVariable,
+        Rule::UnreachableCode,
+        Rule::SelfAssignment,
+        Rule::MismatchedComparison,
+        Rule::ConstantCondition,
+        Rule::EmptyBlock,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::UnusedVariable => "unused-variable",
+            Rule::ShadowedVariable => "shadowed-variable",
+            Rule::UnreachableCode => "unreachable-code",
+            Rule::SelfAssignment => "self-assignment",
+            Rule::MismatchedComparison => "mismatched-comparison",
+            Rule::ConstantCondition => "constant-condition",
+            Rule::EmptyBlock => "empty-block",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Rule> {
+        Rule::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+
+    // Shadowing is often deliberate, so that rule is opt-in.
+    pub fn enabled_by_default(self) -> bool {
+        self != Rule::ShadowedVariable
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub rule: Rule,
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[line {}] Warning: {} [{}]",
+            self.line,
+            self.message,
+            self.rule.name()
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Linter {
+    rules: HashSet<Rule>,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        let rules = Rule::ALL
+            .into_iter()
+            .filter(|rule| rule.enabled_by_default());
+        Linter {
+            rules: rules.collect(),
+        }
+    }
+}
+
+impl Linter {
+    pub fn enable(&mut self, rule: Rule) {
+        self.rules.insert(rule);
+    }
+
+    pub fn disable(&mut self, rule: Rule) {
+        self.rules.remove(&rule);
+    }
+
+    // Lints source, honoring `// lint: allow(rule, ...)` comments. One at the
+    // end of a line covers that line; one on a line of its own covers the
+    // next line of code.
+    pub fn lint(&self, source: &str) -> Result<Vec<Diagnostic>, Error> {
+        let tokens = Scanner::with_trivia(source.to_string())
+            .scan_tokens()
+            .map_err(Error::Scan)?;
+        let statements = Parser::new(tokens.clone()).parse().map_err(Error::Parse)?;
+        let allowed = suppressions(&tokens);
+        let mut diagnostics = self.check(&statements);
+        diagnostics.retain(|diagnostic| {
+            let rules = allowed.get(&diagnostic.line);
+            !rules.is_some_and(|rules| rules.contains(&diagnostic.rule))
+        });
+        Ok(diagnostics)
+    }
+
+    // Runs the enabled rules over a parsed script. Diagnostics come back
+    // sorted by line.
+    pub fn check(&self, statements: &[Statement]) -> Vec<Diagnostic> {
+        let mut checker = Checker {
+            rules: &self.rules,
+            diagnostics: Vec::new(),
+        };
+        checker.statements(statements);
+        checker.bindings(statements);
+        let mut diagnostics = checker.diagnostics;
+        diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+        diagnostics
+    }
+}
+
+// Maps each line to the rules suppressed on it.
+fn suppressions(tokens: &[Token]) -> HashMap<usize, HashSet<Rule>> {
+    let mut allowed: HashMap<usize, HashSet<Rule>> = HashMap::new();
+    for comment in scanner::comments(tokens) {
+        let Some(rules) = allowed_rules(comment.text) else {
+            continue;
+        };
+        let previous_line = comment.token.checked_sub(1).map(|i| tokens[i].line);
+        let line = match previous_line == Some(comment.line) {
+            true => comment.line,
+            false => {
+                let next = &tokens[comment.token];
+                next.line - next.lexeme.matches('\n').count()
+            }
+        };
+        allowed.entry(line).or_default().extend(rules);
+    }
+    allowed
+}
+
+// Reads the rules out of a `// lint: allow(rule, ...)` comment.
+fn allowed_rules(comment: &str) -> Option<Vec<Rule>> {
+    let directive = comment.strip_prefix("//")?.trim();
+    let rules = directive
+        .strip_prefix("lint:")?
+        .trim()
+        .strip_prefix("allow(")?
+        .strip_suffix(')')?;
+    Some(
+        rules
+            .split(',')
+            .filter_map(|rule| Rule::from_name(rule.trim()))
+            .collect(),
+    )
+}
+
+struct Checker<'a> {
+    rules: &'a HashSet<Rule>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Checker<'_> {
+    fn report(&mut self, rule: Rule, line: usize, message: String) {
+        if self.rules.contains(&rule) {
+            self.diagnostics.push(Diagnostic {
+                rule,
+                line,
+                message,
+            });
+        }
+    }
+
+    // The rules that need scopes: unused and shadowed variables.
+    fn bindings(&mut self, statements: &[Statement]) {
+        let resolution = resolver::resolve(statements);
+        for binding in &resolution.bindings {
+            let line = binding.token.line;
+            if binding.name.starts_with('_') {
+                continue;
+            }
+            // Top-level functions are what importers use, and parameters and
+            // catch variables can't be left out.
+            let checked = match binding.kind {
+                BindingKind::Import | BindingKind::Variable => true,
+                BindingKind::Function => !binding.global,
+                BindingKind::Parameter | BindingKind::CatchVariable => false,
+            };
+            if checked && binding.reads.is_empty() {
+                let message = format!("'{}' is never used.", binding.name);
+                self.report(Rule::UnusedVariable, line, message);
+            }
+            if let Some(shadowed) = binding.shadows {
+                let message = format!(
+                    "'{}' shadows the declaration on line {}.",
+                    binding.name, resolution.bindings[shadowed].token.line
+                );
+                self.report(Rule::ShadowedVariable, line, message);
+            }
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        let mut reported = false;
+        for (i, statement) in statements.iter().enumerate() {
+            self.statement(statement);
+            if reported || i + 1 == statements.len() {
+                continue;
+            }
+            let exit = match statement {
+                Statement::Return { keyword, .. }
+                | Statement::Throw { keyword, .. }
+                | Statement::Break { keyword }
+                | Statement::Continue { keyword } => keyword,
+                _ => continue,
+            };
+            let message = format!("Code after '{}' is unreachable.", exit.lexeme);
+            self.report(Rule::UnreachableCode, exit.line, message);
+            reported = true;
+        }
+    }
+
+    fn condition(&mut self, keyword: &Token, condition: &Expr) {
+        // `while (true)` is how loops without a condition are usually written.
+        let infinite_loop = keyword.token_type == TokenType::While
+            && matches!(
+                condition,
+                Expr::Literal {
+                    value: LiteralValue::Bool(true)
+                }
+            );
+        if is_constant(condition) && !infinite_loop {
+            let message = format!("The condition of '{}' is constant.", keyword.lexeme);
+            self.report(Rule::ConstantCondition, keyword.line, message);
+        }
+        self.expr(condition);
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Expr { expr } | Statement::Print { expr, .. } => self.expr(expr),
+            Statement::Var { expr, .. } => self.expr(expr),
+            Statement::Block { brace, statements } => {
+                if statements.is_empty() {
+                    self.report(Rule::EmptyBlock, brace.line, "Empty block.".to_string());
+                }
+                self.statements(statements);
+            }
+            Statement::If {
+                keyword,
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.condition(keyword, condition);
+                self.statement(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch);
+                }
+            }
+            Statement::While {
+                keyword,
+                condition,
+                body,
+            } => {
+                self.condition(keyword, condition);
+                self.statement(body);
+            }
+            Statement::For {
+                keyword,
+                initializer,
+                condition,
+                increment,
+                body,
+            } => {
+                if let Some(initializer) = initializer {
+                    self.statement(initializer);
+                }
+                if let Some(condition) = condition {
+                    self.condition(keyword, condition);
+                }
+                if let Some(increment) = increment {
+                    self.expr(increment);
+                }
+                self.statement(body);
+            }
+            Statement::Break { .. } | Statement::Continue { .. } => {}
+            Statement::Function { body, .. } => self.statements(body),
+            Statement::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            Statement::Throw { value, .. } => self.expr(value),
+            Statement::Try {
+                body,
+                catch_clause,
+                finally_body,
+            } => {
+                self.statements(body);
+                if let Some((name, body)) = catch_clause {
+                    if body.is_empty() {
+                        let message = "Empty catch block discards the error.".to_string();
+                        self.report(Rule::EmptyBlock, name.line, message);
+                    }
+                    self.statements(body);
+                }
+                if let Some(body) = finally_body {
+                    self.statements(body);
+                }
+            }
+            Statement::Import { .. } | Statement::FromImport { .. } => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                if let TokenType::EqualEqual | TokenType::BangEqual = operator.token_type {
+                    self.comparison(left, operator, right);
+                }
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Logical { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Grouping { expression } => self.expr(expression),
+            Expr::Literal { .. } => {}
+            Expr::Unary { right, .. } => self.expr(right),
+            Expr::Var { .. } => {}
+            Expr::Assign { name, value } => {
+                if let Expr::Var { name: source } = ungrouped(value) {
+                    if source.lexeme == name.lexeme {
+                        let message = format!("'{}' is assigned to itself.", name.lexeme);
+                        self.report(Rule::SelfAssignment, name.line, message);
+                    }
+                }
+                self.expr(value);
+            }
+            Expr::Call {
+                callee,
+                arguments,
+                keyword_arguments,
+                ..
+            } => {
+                self.expr(callee);
+                for argument in arguments {
+                    self.expr(argument);
+                }
+                for (_, value) in keyword_arguments {
+                    self.expr(value);
+                }
+            }
+            Expr::Lambda { body, .. } => self.statements(body),
+            Expr::Get { object, .. } => self.expr(object),
+            Expr::Index { object, index, .. } => {
+                self.expr(object);
+                self.expr(index);
+            }
+            Expr::Slice {
+                object, start, end, ..
+            } => {
+                self.expr(object);
+                if let Some(start) = start {
+                    self.expr(start);
+                }
+                if let Some(end) = end {
+                    self.expr(end);
+                }
+            }
+        }
+    }
+
+    // Values of different types are never equal, so comparing against a
+    // literal of another type always gives the same answer.
+    fn comparison(&mut self, left: &Expr, operator: &Token, right: &Expr) {
+        let has_literal = matches!(ungrouped(left), Expr::Literal { .. })
+            || matches!(ungrouped(right), Expr::Literal { .. });
+        if let (true, Some(left_type), Some(right_type)) =
+            (has_literal, static_type(left), static_type(right))
+        {
+            if left_type != right_type {
+                let message = format!(
+                    "Comparing {} with {}: '{}' is always {}.",
+                    left_type,
+                    right_type,
+                    operator.lexeme,
+                    operator.token_type == TokenType::BangEqual
+                );
+                self.report(Rule::MismatchedComparison, operator.line, message);
+            }
+        }
+    }
+}
+
+fn ungrouped(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Grouping { expression } => ungrouped(expression),
+        expr => expr,
+    }
+}
+
+// The type an expression always evaluates to, where that's known without
+// running it.
+fn static_type(expr: &Expr) -> Option<&'static str> {
+    match expr {
+        Expr::Literal { value } => match value {
+            LiteralValue::Integer(_) | LiteralValue::Float(_) => Some("a number"),
+            LiteralValue::String(_) => Some("a string"),
+            LiteralValue::Bool(_) => Some("a bool"),
+            LiteralValue::None => Some("nil"),
+            _ => None,
+        },
+        Expr::Grouping { expression } => static_type(expression),
+        Expr::Unary { operator, right } => match operator.token_type {
+            TokenType::Bang => Some("a bool"),
+            _ => static_type(right).filter(|kind| *kind == "a number"),
+        },
+        Expr::Binary {
+            left,
+            operator,
+            right,
+        } => match operator.token_type {
+            TokenType::EqualEqual
+            | TokenType::BangEqual
+            | TokenType::Less
+            | TokenType::LessEqual
+            | TokenType::Greater
+            | TokenType::GreaterEqual => Some("a bool"),
+            TokenType::Plus | TokenType::Minus | TokenType::Star | TokenType::Slash => {
+                let kind = static_type(left)?;
+                let same = static_type(right)? == kind;
+                match operator.token_type {
+                    TokenType::Plus if same && kind == "a string" => Some(kind),
+                    _ if same && kind == "a number" => Some(kind),
+                    _ => None,
+                }
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Built only from literals, so it evaluates the same way every time.
+fn is_constant(expr: &Expr) -> bool {
+    match expr {
+        Expr::Literal { .. } => true,
+        Expr::Grouping { expression } => is_constant(expression),
+        Expr::Unary { right, .. } => is_constant(right),
+        Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+            is_constant(left) && is_constant(right)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (rule, line) of each diagnostic the default rules give.
+    fn lint(source: &str) -> Vec<(Rule, usize)> {
+        lint_with(&Linter::default(), source)
+    }
+
+    fn lint_with(linter: &Linter, source: &str) -> Vec<(Rule, usize)> {
+        let diagnostics = linter.lint(source).unwrap();
+        diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.rule, diagnostic.line))
+            .collect()
+    }
+
+    #[test]
+    fn unused_variables() {
+        let source =
+            "var unused = 1;\nvar used = 2;\nprint used;\nfun f(p) {\n  var local = p;\n}\nf(1);\n";
+        assert_eq!(
+            lint(source),
+            [(Rule::UnusedVariable, 1), (Rule::UnusedVariable, 5)]
+        );
+        // Writes alone don't count as uses, but reads from any function do.
+        let source = "var written = 1;\nwritten = 2;\nfun g() {\n  return later;\n}\nvar later = 3;\nprint g();\n";
+        assert_eq!(lint(source), [(Rule::UnusedVariable, 1)]);
+    }
+
+    #[test]
+    fn unused_variable_exemptions() {
+        // Top-level functions may be exported, parameters and catch
+        // variables can't be dropped, and `_` marks deliberate non-use.
+        let source = "fun exported(unused_param) {}\nvar _ignored = 1;\ntry {\n  print 1;\n} catch (e) {\n  print 2;\n}\n";
+        assert_eq!(lint(source), []);
+    }
+
+    #[test]
+    fn unused_imports() {
+        assert_eq!(
+            lint("import \"lib/utils\";\nimport math;\nprint math.pi;\n"),
+            [(Rule::UnusedVariable, 1)]
+        );
+    }
+
+    #[test]
+    fn shadowed_variables_are_opt_in() {
+        let source = "var a = 1;\n{\n  var a = 2;\n  print a;\n}\nprint a;\n";
+        assert_eq!(lint(source), []);
+        let mut linter = Linter::default();
+        linter.enable(Rule::ShadowedVariable);
+        assert_eq!(lint_with(&linter, source), [(Rule::ShadowedVariable, 3)]);
+        assert_eq!(
+            lint_with(&linter, "var a = 1;\nvar b = 2;\nprint a + b;\n"),
+            []
+        );
+    }
+
+    #[test]
+    fn unreachable_code() {
+        let source = "fun f() {\n  return 1;\n  print 2;\n}\nwhile (true) {\n  break;\n  print 3;\n}\nprint f();\n";
+        assert_eq!(
+            lint(source),
+            [(Rule::UnreachableCode, 2), (Rule::UnreachableCode, 6)]
+        );
+        assert_eq!(
+            lint("fun f() {\n  print 1;\n  return 2;\n}\nprint f();\n"),
+            []
+        );
+    }
+
+    #[test]
+    fn self_assignment() {
+        let source = "var a = 1;\na = a;\na = (a);\na = a + 1;\nprint a;\n";
+        assert_eq!(
+            lint(source),
+            [(Rule::SelfAssignment, 2), (Rule::SelfAssignment, 3)]
+        );
+    }
+
+    #[test]
+    fn mismatched_comparison() {
+        let source =
+            "var a = 1;\nprint a == \"1\";\nprint \"x\" != 2;\nprint a == 2;\nprint a == nil;\n";
+        let diagnostics = Linter::default().lint(source).unwrap();
+        let found: Vec<(usize, &str)> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [(3, "Comparing a string with a number: '!=' is always true.")]
+        );
+        assert_eq!(
+            lint("print 1 == \"1\";\n"),
+            [(Rule::MismatchedComparison, 1)]
+        );
+    }
+
+    #[test]
+    fn constant_condition() {
+        let source = "if (true) print 1;\nwhile (false) print 2;\nfor (; 1 < 2;) print 3;\n";
+        assert_eq!(
+            lint(source),
+            [
+                (Rule::ConstantCondition, 1),
+                (Rule::ConstantCondition, 2),
+                (Rule::ConstantCondition, 3),
+            ]
+        );
+        assert_eq!(
+            lint("var a = 1;\nif (a) print a;\nwhile (true) break;\n"),
+            []
+        );
+    }
+
+    #[test]
+    fn empty_block() {
+        let source = "{}\ntry {\n  print 1;\n} catch (e) {}\n";
+        assert_eq!(lint(source), [(Rule::EmptyBlock, 1), (Rule::EmptyBlock, 4)]);
+        assert_eq!(lint("{\n  print 1;\n}\n"), []);
+    }
+
+    #[test]
+    fn disabled_rules_are_not_reported() {
+        let mut linter = Linter::default();
+        linter.disable(Rule::UnusedVariable);
+        assert_eq!(
+            lint_with(&linter, "var a = 1;\n{}\n"),
+            [(Rule::EmptyBlock, 2)]
+        );
+        linter.disable(Rule::EmptyBlock);
+        assert_eq!(lint_with(&linter, "var a = 1;\n{}\n"), []);
+    }
+
+    #[test]
+    fn allow_comments_suppress_rules() {
+        // At the end of a line it covers that line.
+        let source = "var a = 1; // lint: allow(unused-variable)\nvar b = 2;\n";
+        assert_eq!(lint(source), [(Rule::UnusedVariable, 2)]);
+        // On a line of its own it covers the next line of code.
+        let source = "// lint: allow(unused-variable, empty-block)\n\nvar a = 1;\n{}\n";
+        assert_eq!(lint(source), [(Rule::EmptyBlock, 4)]);
+        // Other rules on the line are still reported.
+        let source = "// lint: allow(empty-block)\nvar a = 1;\n";
+        assert_eq!(lint(source), [(Rule::UnusedVariable, 2)]);
+    }
+
+    #[test]
+    fn rule_names_round_trip() {
+        for rule in Rule::ALL {
+            assert_eq!(Rule::from_name(rule.name()), Some(rule));
+        }
+        assert_eq!(Rule::from_name("no-such-rule"), None);
+    }
+}
@@ -0,0 +1,872 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead, ErrorKind, Write};
+use std::iter;
+use std::rc::Rc;
+
+use crate::common::{LiteralValue, Token, TokenType};
+use crate::convert::{FromValue, IntoValue};
+use crate::error::Error;
+use crate::lint::Linter;
+use crate::parser::Parser;
+use crate::resolver::{self, Binding, BindingKind, Resolution};
+use crate::scanner::{self, Scanner};
+use crate::stdlib::{parse_json, to_json};
+
+// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+// Larger Content-Lengths are refused rather than allocated.
+const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+const SEVERITY_ERROR: i64 = 1;
+const SEVERITY_WARNING: i64 = 2;
+
+const SYMBOL_MODULE: i64 = 2;
+const SYMBOL_FUNCTION: i64 = 12;
+const SYMBOL_VARIABLE: i64 = 13;
+
+// Indices into this list are the token types sent to the client.
+const TOKEN_TYPES: [&str; 9] = [
+    "keyword",
+    "variable",
+    "function",
+    "parameter",
+    "namespace",
+    "property",
+    "string",
+    "number",
+    "comment",
+];
+
+// Serves the Language Server Protocol over a pair of streams until the client
+// sends `exit`. The exit code is 0 if `shutdown` came first, as the protocol
+// asks, and 1 otherwise.
+pub fn serve(mut input: impl BufRead, output: impl Write) -> io::Result<i32> {
+    let mut server = Server {
+        output,
+        documents: HashMap::new(),
+        shut_down: false,
+    };
+    while let Some(body) = read_message(&mut input)? {
+        let message = match parse_json(&body) {
+            Ok(message) => message,
+            Err(error) => {
+                let error = ResponseError::new(PARSE_ERROR, error.message);
+                server.respond(LiteralValue::None, Err(error))?;
+                continue;
+            }
+        };
+        // Messages without a method are responses; the server sends no
+        // requests, so there are none to wait for.
+        let Ok(method) = String::from_value(&get(&message, "method")) else {
+            continue;
+        };
+        if method == "exit" {
+            return Ok(if server.shut_down { 0 } else { 1 });
+        }
+        let params = get(&message, "params");
+        match has_key(&message, "id") {
+            true => {
+                let result = server.request(&method, &params);
+                server.respond(get(&message, "id"), result)?;
+            }
+            false => server.notification(&method, &params)?,
+        }
+    }
+    Ok(1)
+}
+
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let Some(length) = length else {
+        let message = "message without a Content-Length header";
+        return Err(io::Error::new(ErrorKind::InvalidData, message));
+    };
+    if length > MAX_MESSAGE_SIZE {
+        let message = format!(
+            "message of {} bytes is over the {} byte limit",
+            length, MAX_MESSAGE_SIZE
+        );
+        return Err(io::Error::new(ErrorKind::InvalidData, message));
+    }
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|error| io::Error::new(ErrorKind::InvalidData, error))
+}
+
+struct ResponseError {
+    code: i64,
+    message: String,
+}
+impl ResponseError {
+    fn new(code: i64, message: String) -> Self {
+        ResponseError { code, message }
+    }
+}
+
+struct Server<W> {
+    output: W,
+    documents: HashMap<String, Document>,
+    shut_down: bool,
+}
+
+impl<W: Write> Server<W> {
+    fn send(&mut self, mut entries: Vec<(&str, LiteralValue)>) -> io::Result<()> {
+        entries.insert(0, ("jsonrpc", "2.0".into_value()));
+        // Messages are built from JSON values only, so this can't fail.
+        let body = to_json(&object(entries)).unwrap();
+        write!(
+            self.output,
+            "Content-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )?;
+        self.output.flush()
+    }
+
+    fn respond(
+        &mut self,
+        id: LiteralValue,
+        result: Result<LiteralValue, ResponseError>,
+    ) -> io::Result<()> {
+        match result {
+            Ok(result) => self.send(vec![("id", id), ("result", result)]),
+            Err(error) => {
+                let error = object(vec![
+                    ("code", error.code.into_value()),
+                    ("message", error.message.into_value()),
+                ]);
+                self.send(vec![("id", id), ("error", error)])
+            }
+        }
+    }
+
+    fn request(
+        &mut self,
+        method: &str,
+        params: &LiteralValue,
+    ) -> Result<LiteralValue, ResponseError> {
+        if self.shut_down {
+            let message = format!("Received '{}' after shutdown.", method);
+            return Err(ResponseError::new(INVALID_REQUEST, message));
+        }
+        match method {
+            "initialize" => Ok(initialize_result()),
+            "shutdown" => {
+                self.shut_down = true;
+                Ok(LiteralValue::None)
+            }
+            "textDocument/definition" => {
+                let (document, uri, offset) = self.position(params)?;
+                let location = document
+                    .binding_at(offset)
+                    .map(|binding| document.location(&uri, &binding.token));
+                Ok(location.into_value())
+            }
+            "textDocument/references" => {
+                let (document, uri, offset) = self.position(params)?;
+                let declaration =
+                    param::<Option<bool>>(params, &["context", "includeDeclaration"])?;
+                let Some(binding) = document.binding_at(offset) else {
+                    return Ok(Vec::<LiteralValue>::new().into_value());
+                };
+                let declaration = declaration.unwrap_or(true).then_some(&binding.token);
+                let mut tokens: Vec<&Token> = declaration
+                    .into_iter()
+                    .chain(&binding.reads)
+                    .chain(&binding.writes)
+                    .collect();
+                tokens.sort_by_key(|token| token.offset);
+                let locations = tokens
+                    .into_iter()
+                    .map(|token| document.location(&uri, token));
+                Ok(locations.collect::<Vec<_>>().into_value())
+            }
+            "textDocument/hover" => {
+                let (document, _, offset) = self.position(params)?;
+                Ok(document.hover(offset).into_value())
+            }
+            "textDocument/documentSymbol" => {
+                let (document, uri) = self.document(params)?;
+                Ok(document.symbols(&uri).into_value())
+            }
+            "textDocument/semanticTokens/full" => {
+                let (document, _) = self.document(params)?;
+                let data = document.semantic_tokens().into_value();
+                Ok(object(vec![("data", data)]))
+            }
+            _ => {
+                let message = format!("Unknown method '{}'.", method);
+                Err(ResponseError::new(METHOD_NOT_FOUND, message))
+            }
+        }
+    }
+
+    // Documents are synced in full: every change sends the whole text.
+    fn notification(&mut self, method: &str, params: &LiteralValue) -> io::Result<()> {
+        let uri = param::<String>(params, &["textDocument", "uri"]);
+        let text = match method {
+            "textDocument/didOpen" => param::<String>(params, &["textDocument", "text"]),
+            "textDocument/didChange" => param::<Vec<LiteralValue>>(params, &["contentChanges"])
+                .and_then(|changes| {
+                    let change = changes.last().cloned().unwrap_or(LiteralValue::None);
+                    param::<String>(&change, &["text"])
+                }),
+            "textDocument/didClose" => {
+                if let Ok(uri) = uri {
+                    self.documents.remove(&uri);
+                    self.publish_diagnostics(uri, Vec::new())?;
+                }
+                return Ok(());
+            }
+            _ => return Ok(()),
+        };
+        if let (Ok(uri), Ok(text)) = (uri, text) {
+            let document = Document::new(text);
+            let diagnostics = document.diagnostics();
+            self.documents.insert(uri.clone(), document);
+            self.publish_diagnostics(uri, diagnostics)?;
+        }
+        Ok(())
+    }
+
+    fn publish_diagnostics(
+        &mut self,
+        uri: String,
+        diagnostics: Vec<LiteralValue>,
+    ) -> io::Result<()> {
+        let params = object(vec![
+            ("uri", uri.into_value()),
+            ("diagnostics", diagnostics.into_value()),
+        ]);
+        self.send(vec![
+            ("method", "textDocument/publishDiagnostics".into_value()),
+            ("params", params),
+        ])
+    }
+
+    fn document(&self, params: &LiteralValue) -> Result<(&Document, String), ResponseError> {
+        let uri = param::<String>(params, &["textDocument", "uri"])?;
+        match self.documents.get(&uri) {
+            Some(document) => Ok((document, uri)),
+            None => {
+                let message = format!("Document '{}' is not open.", uri);
+                Err(ResponseError::new(INVALID_PARAMS, message))
+            }
+        }
+    }
+
+    fn position(&self, params: &LiteralValue) -> Result<(&Document, String, usize), ResponseError> {
+        let (document, uri) = self.document(params)?;
+        let line = param::<i64>(params, &["position", "line"])?;
+        let character = param::<i64>(params, &["position", "character"])?;
+        let offset = document.offset(line.max(0) as usize, character.max(0) as usize);
+        Ok((document, uri, offset))
+    }
+}
+
+fn initialize_result() -> LiteralValue {
+    let legend = object(vec![
+        ("tokenTypes", TOKEN_TYPES.to_vec().into_value()),
+        ("tokenModifiers", Vec::<String>::new().into_value()),
+    ]);
+    let capabilities = object(vec![
+        // Full text on open and on every change.
+        ("textDocumentSync", 1i64.into_value()),
+        ("definitionProvider", true.into_value()),
+        ("referencesProvider", true.into_value()),
+        ("hoverProvider", true.into_value()),
+        ("documentSymbolProvider", true.into_value()),
+        (
+            "semanticTokensProvider",
+            object(vec![("legend", legend), ("full", true.into_value())]),
+        ),
+    ]);
+    let server_info = object(vec![
+        ("name", "ownpy".into_value()),
+        ("version", env!("CARGO_PKG_VERSION").into_value()),
+    ]);
+    object(vec![
+        ("capabilities", capabilities),
+        ("serverInfo", server_info),
+    ])
+}
+
+// An open file and what the analysis found in it. The resolution is only
+// there while the text parses.
+struct Document {
+    text: String,
+    // Byte offset where each line starts.
+    line_starts: Vec<usize>,
+    tokens: Vec<Token>,
+    error: Option<Error>,
+    resolution: Option<Resolution>,
+}
+
+impl Document {
+    fn new(text: String) -> Self {
+        let line_starts = iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        let mut document = Document {
+            text,
+            line_starts,
+            tokens: Vec::new(),
+            error: None,
+            resolution: None,
+        };
+        match Scanner::with_trivia(document.text.clone()).scan_tokens() {
+            Ok(tokens) => document.tokens = tokens,
+            Err(errors) => document.error = Some(Error::Scan(errors)),
+        }
+        if document.error.is_none() {
+            match Parser::new(document.tokens.clone()).parse() {
+                Ok(statements) => document.resolution = Some(resolver::resolve(&statements)),
+                Err(errors) => document.error = Some(Error::Parse(errors)),
+            }
+        }
+        document
+    }
+
+    // Zero-based, as the protocol counts lines.
+    fn line(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset) - 1
+    }
+
+    // Positions count UTF-16 code units, the protocol's default encoding.
+    fn position(&self, offset: usize) -> LiteralValue {
+        let line = self.line(offset);
+        let start = self.line_starts[line];
+        let character = self.text[start..offset].encode_utf16().count();
+        object(vec![
+            ("line", (line as i64).into_value()),
+            ("character", (character as i64).into_value()),
+        ])
+    }
+
+    fn offset(&self, line: usize, character: usize) -> usize {
+        let Some(&start) = self.line_starts.get(line) else {
+            return self.text.len();
+        };
+        let mut units = 0;
+        for (i, c) in self.text[start..].char_indices() {
+            if units >= character || c == '\n' {
+                return start + i;
+            }
+            units += c.len_utf16();
+        }
+        self.text.len()
+    }
+
+    fn range(&self, start: usize, end: usize) -> LiteralValue {
+        object(vec![
+            ("start", self.position(start)),
+            ("end", self.position(end)),
+        ])
+    }
+
+    fn token_range(&self, token: &Token) -> LiteralValue {
+        self.range(token.offset, token.offset + token.lexeme.len())
+    }
+
+    // The text of a line without its indentation or line break, for
+    // diagnostics that only know their line.
+    fn line_range(&self, line: usize) -> LiteralValue {
+        let line = line.saturating_sub(1).min(self.line_starts.len() - 1);
+        let start = self.line_starts[line];
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .map_or(self.text.len(), |&next| next - 1);
+        let text = self.text[start..end].trim_end_matches('\r');
+        let indent = text.len() - text.trim_start().len();
+        self.range(start + indent, start + text.len())
+    }
+
+    fn location(&self, uri: &str, token: &Token) -> LiteralValue {
+        object(vec![
+            ("uri", uri.into_value()),
+            ("range", self.token_range(token)),
+        ])
+    }
+
+    fn diagnostics(&self) -> Vec<LiteralValue> {
+        let diagnostic = |range, severity: i64, message: &str, code: Option<&str>| {
+            let mut entries = vec![
+                ("range", range),
+                ("severity", severity.into_value()),
+                ("source", "ownpy".into_value()),
+                ("message", message.into_value()),
+            ];
+            entries.extend(code.map(|code| ("code", code.into_value())));
+            object(entries)
+        };
+        match &self.error {
+            Some(Error::Scan(errors)) => errors
+                .iter()
+                .map(|error| {
+                    let range = self.line_range(error.line);
+                    diagnostic(range, SEVERITY_ERROR, &error.message, None)
+                })
+                .collect(),
+            Some(Error::Parse(errors)) => errors
+                .iter()
+                .map(|error| {
+                    let range = self.token_range(&error.token);
+                    diagnostic(range, SEVERITY_ERROR, &error.message, None)
+                })
+                .collect(),
+            _ => Linter::default()
+                .lint(&self.text)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|warning| {
+                    let range = self.line_range(warning.line);
+                    let code = Some(warning.rule.name());
+                    diagnostic(range, SEVERITY_WARNING, &warning.message, code)
+                })
+                .collect(),
+        }
+    }
+
+    // The binding whose declaration or use covers `offset`. A cursor just
+    // after a name still counts as on it.
+    fn binding_at(&self, offset: usize) -> Option<&Binding> {
+        let covers =
+            |token: &Token| (token.offset..=token.offset + token.lexeme.len()).contains(&offset);
+        self.resolution.as_ref()?.bindings.iter().find(|binding| {
+            iter::once(&binding.token)
+                .chain(&binding.reads)
+                .chain(&binding.writes)
+                .any(covers)
+        })
+    }
+
+    fn hover(&self, offset: usize) -> Option<LiteralValue> {
+        let binding = self.binding_at(offset)?;
+        let line = self.line(binding.token.offset);
+        let start = self.line_starts[line];
+        let declaration = self.text[start..].lines().next().unwrap_or_default().trim();
+        let kind = match binding.kind {
+            BindingKind::Variable => "variable",
+            BindingKind::Function => "function",
+            BindingKind::Parameter => "parameter",
+            BindingKind::CatchVariable => "caught error",
+            BindingKind::Import => "import",
+        };
+        let value = format!(
+            "```ownpy\n{}\n```\n{} `{}`, declared on line {}",
+            declaration,
+            kind,
+            binding.name,
+            line + 1
+        );
+        let contents = object(vec![
+            ("kind", "markdown".into_value()),
+            ("value", value.into_value()),
+        ]);
+        Some(object(vec![("contents", contents)]))
+    }
+
+    // Functions at any depth, and the variables and imports a script declares
+    // at the top level.
+    fn symbols(&self, uri: &str) -> Vec<LiteralValue> {
+        let Some(resolution) = &self.resolution else {
+            return Vec::new();
+        };
+        let symbols = resolution.bindings.iter().filter_map(|binding| {
+            let kind = match binding.kind {
+                BindingKind::Function => SYMBOL_FUNCTION,
+                BindingKind::Variable if binding.global => SYMBOL_VARIABLE,
+                BindingKind::Import if binding.global => SYMBOL_MODULE,
+                _ => return None,
+            };
+            Some(object(vec![
+                ("name", binding.name.as_str().into_value()),
+                ("kind", kind.into_value()),
+                ("location", self.location(uri, &binding.token)),
+            ]))
+        });
+        symbols.collect()
+    }
+
+    // Encodes each token as five integers: line and start relative to the
+    // previous token, length, type and modifiers. Tokens that span lines are
+    // split, since clients needn't support multi-line tokens.
+    fn semantic_tokens(&self) -> Vec<i64> {
+        let mut kinds = HashMap::new();
+        if let Some(resolution) = &self.resolution {
+            for binding in &resolution.bindings {
+                let uses = iter::once(&binding.token)
+                    .chain(&binding.reads)
+                    .chain(&binding.writes);
+                for token in uses {
+                    kinds.insert(token.offset, binding.kind);
+                }
+            }
+        }
+        // Comments come from the tokens' trivia, in front of the token they precede.
+        let mut comments = scanner::comments(&self.tokens).into_iter().peekable();
+        let mut spans = Vec::new();
+        let mut previous_type = None;
+        for (i, token) in self.tokens.iter().enumerate() {
+            while let Some(comment) = comments.next_if(|comment| comment.token == i) {
+                spans.extend(
+                    type_index("comment").map(|index| (comment.offset, comment.text, index)),
+                );
+            }
+            let token_type = semantic_type(token, previous_type, &kinds);
+            previous_type = Some(token.token_type.clone());
+            spans.extend(token_type.map(|index| (token.offset, token.lexeme.as_str(), index)));
+        }
+        let mut data = Vec::new();
+        let (mut previous_line, mut previous_start) = (0, 0);
+        for (mut offset, text, token_type) in spans {
+            for piece in text.split('\n') {
+                let text = piece.trim_end_matches('\r');
+                let length = text.encode_utf16().count();
+                if length > 0 {
+                    let line = self.line(offset);
+                    let start = self.text[self.line_starts[line]..offset]
+                        .encode_utf16()
+                        .count();
+                    if line != previous_line {
+                        previous_start = 0;
+                    }
+                    data.extend([
+                        (line - previous_line) as i64,
+                        (start - previous_start) as i64,
+                        length as i64,
+                        token_type as i64,
+                        0,
+                    ]);
+                    (previous_line, previous_start) = (line, start);
+                }
+                offset += piece.len() + 1;
+            }
+        }
+        data
+    }
+}
+
+fn semantic_type(
+    token: &Token,
+    previous: Option<TokenType>,
+    kinds: &HashMap<usize, BindingKind>,
+) -> Option<usize> {
+    let name = match token.token_type {
+        TokenType::Identifier => match kinds.get(&token.offset) {
+            Some(BindingKind::Function) => "function",
+            Some(BindingKind::Parameter) => "parameter",
+            Some(BindingKind::Import) => "namespace",
+            Some(BindingKind::Variable | BindingKind::CatchVariable) => "variable",
+            None if previous == Some(TokenType::Dot) => "property",
+            None => "variable",
+        },
+        TokenType::String => "string",
+        TokenType::Number => "number",
+        TokenType::And
+        | TokenType::Class
+        | TokenType::Else
+        | TokenType::False
+        | TokenType::Fun
+        | TokenType::For
+        | TokenType::If
+        | TokenType::Nil
+        | TokenType::Or
+        | TokenType::Print
+        | TokenType::Return
+        | TokenType::Super
+        | TokenType::This
+        | TokenType::True
+        | TokenType::Var
+        | TokenType::While
+        | TokenType::Break
+        | TokenType::Continue
+        | TokenType::Throw
+        | TokenType::Try
+        | TokenType::Catch
+        | TokenType::Finally
+        | TokenType::Import
+        | TokenType::From
+        | TokenType::As => "keyword",
+        _ => return None,
+    };
+    type_index(name)
+}
+
+fn type_index(name: &str) -> Option<usize> {
+    TOKEN_TYPES
+        .iter()
+        .position(|token_type| *token_type == name)
+}
+
+// JSON objects arrive and leave as maps, the same values `json.parse` gives
+// scripts.
+fn object(entries: Vec<(&str, LiteralValue)>) -> LiteralValue {
+    let entries: BTreeMap<String, LiteralValue> = entries
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+    LiteralValue::Map(Rc::new(RefCell::new(entries)))
+}
+
+fn has_key(value: &LiteralValue, key: &str) -> bool {
+    matches!(value, LiteralValue::Map(entries) if entries.borrow().contains_key(key))
+}
+
+// Missing fields read as nil.
+fn get(value: &LiteralValue, key: &str) -> LiteralValue {
+    match value {
+        LiteralValue::Map(entries) => entries
+            .borrow()
+            .get(key)
+            .cloned()
+            .unwrap_or(LiteralValue::None),
+        _ => LiteralValue::None,
+    }
+}
+
+fn param<T: FromValue>(params: &LiteralValue, path: &[&str]) -> Result<T, ResponseError> {
+    let value = path
+        .iter()
+        .fold(params.clone(), |value, key| get(&value, key));
+    T::from_value(&value).map_err(|error| {
+        let message = format!("Invalid '{}': {}", path.join("."), error);
+        ResponseError::new(INVALID_PARAMS, message)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URI: &str = "file:///test.ownpy";
+
+    fn frame(body: &str) -> String {
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    fn request(id: i64, method: &str, params: &str) -> String {
+        frame(&format!(
+            r#"{{"jsonrpc":"2.0","id":{},"method":"{}","params":{}}}"#,
+            id, method, params
+        ))
+    }
+
+    fn notification(method: &str, params: &str) -> String {
+        frame(&format!(
+            r#"{{"jsonrpc":"2.0","method":"{}","params":{}}}"#,
+            method, params
+        ))
+    }
+
+    fn open(text: &str) -> String {
+        let mut text_json = String::new();
+        crate::stdlib::write_string(&mut text_json, text);
+        let params = format!(
+            r#"{{"textDocument":{{"uri":"{}","languageId":"ownpy","version":1,"text":{}}}}}"#,
+            URI, text_json
+        );
+        notification("textDocument/didOpen", &params)
+    }
+
+    fn position(line: i64, character: i64) -> String {
+        format!(
+            r#"{{"textDocument":{{"uri":"{}"}},"position":{{"line":{},"character":{}}}}}"#,
+            URI, line, character
+        )
+    }
+
+    // Runs a session and returns the exit code and every message sent back.
+    fn session(input: &[String]) -> (i32, Vec<LiteralValue>) {
+        let input = input.concat();
+        let mut output = Vec::new();
+        let code = serve(input.as_bytes(), &mut output).unwrap();
+        let mut messages = Vec::new();
+        let mut rest = output.as_slice();
+        while !rest.is_empty() {
+            let body = read_message(&mut rest).unwrap().unwrap();
+            messages.push(parse_json(&body).unwrap());
+        }
+        (code, messages)
+    }
+
+    fn field(value: &LiteralValue, path: &str) -> LiteralValue {
+        path.split('.')
+            .fold(value.clone(), |value, key| match &value {
+                LiteralValue::List(items) => items.borrow()[key.parse::<usize>().unwrap()].clone(),
+                _ => get(&value, key),
+            })
+    }
+
+    fn text(value: &LiteralValue, path: &str) -> String {
+        field(value, path).to_string()
+    }
+
+    #[test]
+    fn initialize_shutdown_and_exit() {
+        let (code, messages) = session(&[
+            request(1, "initialize", "{}"),
+            notification("initialized", "{}"),
+            request(2, "shutdown", "null"),
+            notification("exit", "null"),
+        ]);
+        assert_eq!(code, 0);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(text(&messages[0], "id"), "1");
+        assert_eq!(text(&messages[0], "result.serverInfo.name"), "ownpy");
+        assert_eq!(
+            text(&messages[0], "result.capabilities.hoverProvider"),
+            "true"
+        );
+        assert_eq!(text(&messages[1], "id"), "2");
+        assert!(has_key(&messages[1], "result"));
+    }
+
+    #[test]
+    fn exit_without_shutdown_fails() {
+        let (code, _) = session(&[notification("exit", "null")]);
+        assert_eq!(code, 1);
+        let (code, _) = session(&[request(1, "initialize", "{}")]);
+        assert_eq!(code, 1);
+    }
+
+    #[test]
+    fn requests_after_shutdown_are_invalid() {
+        let (_, messages) = session(&[
+            request(1, "shutdown", "null"),
+            request(2, "initialize", "{}"),
+        ]);
+        assert_eq!(
+            text(&messages[1], "error.code"),
+            INVALID_REQUEST.to_string()
+        );
+    }
+
+    #[test]
+    fn opening_a_document_publishes_diagnostics() {
+        let (_, messages) = session(&[open("var unused = 1;\nprint (;\n")]);
+        assert_eq!(
+            text(&messages[0], "method"),
+            "textDocument/publishDiagnostics"
+        );
+        assert_eq!(text(&messages[0], "params.uri"), URI);
+        let diagnostic = field(&messages[0], "params.diagnostics.0");
+        assert_eq!(text(&diagnostic, "severity"), SEVERITY_ERROR.to_string());
+        assert_eq!(text(&diagnostic, "range.start.line"), "1");
+        assert_eq!(text(&diagnostic, "message"), "Expect expression.");
+
+        let (_, messages) = session(&[open("var unused = 1;\n")]);
+        let diagnostic = field(&messages[0], "params.diagnostics.0");
+        assert_eq!(text(&diagnostic, "severity"), SEVERITY_WARNING.to_string());
+        assert_eq!(text(&diagnostic, "code"), "unused-variable");
+        assert_eq!(text(&diagnostic, "range.start.line"), "0");
+    }
+
+    #[test]
+    fn closing_a_document_clears_its_diagnostics() {
+        let close = format!(r#"{{"textDocument":{{"uri":"{}"}}}}"#, URI);
+        let (_, messages) = session(&[
+            open("var unused = 1;\n"),
+            notification("textDocument/didClose", &close),
+            request(1, "textDocument/hover", &position(0, 4)),
+        ]);
+        assert_eq!(text(&messages[1], "params.diagnostics"), "[]");
+        assert_eq!(text(&messages[2], "error.code"), INVALID_PARAMS.to_string());
+    }
+
+    #[test]
+    fn hover_and_definition() {
+        let source = "fun square(x) {\n  return x * x;\n}\nprint square(3);\n";
+        let (_, messages) = session(&[
+            open(source),
+            request(1, "textDocument/hover", &position(3, 8)),
+            request(2, "textDocument/definition", &position(3, 8)),
+            request(3, "textDocument/definition", &position(1, 9)),
+            request(4, "textDocument/hover", &position(3, 0)),
+        ]);
+        let hover = text(&messages[1], "result.contents.value");
+        assert!(hover.contains("fun square(x) {"), "{}", hover);
+        assert!(
+            hover.contains("function `square`, declared on line 1"),
+            "{}",
+            hover
+        );
+
+        let definition = field(&messages[2], "result");
+        assert_eq!(text(&definition, "uri"), URI);
+        assert_eq!(text(&definition, "range.start.line"), "0");
+        assert_eq!(text(&definition, "range.start.character"), "4");
+        assert_eq!(text(&definition, "range.end.character"), "10");
+
+        let parameter = field(&messages[3], "result");
+        assert_eq!(text(&parameter, "range.start.line"), "0");
+        assert_eq!(text(&parameter, "range.start.character"), "11");
+
+        // Nothing is declared under a keyword.
+        assert_eq!(field(&messages[4], "result"), LiteralValue::None);
+    }
+
+    #[test]
+    fn unknown_methods_and_bad_json_get_errors() {
+        let (_, messages) = session(&[
+            request(1, "no/such/method", "{}"),
+            frame("{not json"),
+            notification("no/such/notification", "{}"),
+        ]);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            text(&messages[0], "error.code"),
+            METHOD_NOT_FOUND.to_string()
+        );
+        assert_eq!(text(&messages[1], "error.code"), PARSE_ERROR.to_string());
+        assert_eq!(field(&messages[1], "id"), LiteralValue::None);
+    }
+
+    #[test]
+    fn bad_frames_stop_the_server() {
+        for input in [
+            "Content-Type: text/plain\r\n\r\n{}",
+            "Content-Length: many\r\n\r\n{}",
+            "Content-Length: 99999999999999\r\n\r\n",
+            "Content-Length: 10\r\n\r\n{}",
+        ] {
+            let error = serve(input.as_bytes(), Vec::new()).unwrap_err();
+            assert!(
+                matches!(
+                    error.kind(),
+                    ErrorKind::InvalidData | ErrorKind::UnexpectedEof
+                ),
+                "{:?}",
+                error
+            );
+        }
+        let body = [0xff, 0xfe];
+        let mut input = b"Content-Length: 2\r\n\r\n".to_vec();
+        input.extend(body);
+        let error = serve(input.as_slice(), Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -1,103 +1,409 @@
+use std::collections::HashSet;
 use std::env;
-use std::fs::read_to_string;
-use std::io;
+use std::fs::{self, read_to_string};
+use std::io::{self, Read, Write};
 use std::path::Path;
+use std::process;
 
-use self::interpreter::Interpreter;
+use clap::error::ErrorKind;
+use clap::{Args, Parser as _, Subcommand, ValueEnum};
+use ownpy::error::ParserError;
+use ownpy::lint::{Linter, Rule};
+use ownpy::lsp;
+use ownpy::{
+    formatter, printer, resolver, Engine, Error, Parser, Scanner, Statement, StderrReporter,
+};
 
-mod common;
-mod environment;
-mod error;
-mod interpreter;
-mod parser;
-mod scanner;
-mod utils;
+mod repl;
+
+// Exit codes follow sysexits.h.
+const EX_OK: i32 = 0;
+const EX_USAGE: i32 = 64;
+const EX_DATAERR: i32 = 65;
+const EX_NOINPUT: i32 = 66;
+const EX_SOFTWARE: i32 = 70;
+const EX_IOERR: i32 = 74;
+// Like other formatters, `fmt --check` exits with 1 when a file needs formatting.
+const EX_UNFORMATTED: i32 = 1;
+// `lint` does the same when it finds a problem.
+const EX_WARNINGS: i32 = 1;
+
+#[derive(clap::Parser)]
+#[command(
+    name = "ownpy",
+    version,
+    about = "Run ownpy scripts, or start a REPL when no script is given.",
+    args_conflicts_with_subcommands = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run a script
+    Run(RunArgs),
+    /// Rewrite scripts in the canonical style
+    ///
+    /// Argument and parameter lists that would run past 100 columns are
+    /// broken with one item per line; other long lines are left as they are.
+    Fmt(FmtArgs),
+    /// Check scripts for likely mistakes without running them
+    Lint(LintArgs),
+    /// Serve the Language Server Protocol over stdin and stdout
+    Lsp,
+}
+
+#[derive(Args)]
+struct FmtArgs {
+    /// List the files that need formatting instead of rewriting them
+    #[arg(long)]
+    check: bool,
+    /// Files to format; with none, or `-`, formats stdin to stdout
+    files: Vec<String>,
+}
+
+#[derive(Args)]
+struct LintArgs {
+    /// Turn on a rule: unused-variable, shadowed-variable, unreachable-code,
+    /// self-assignment, mismatched-comparison, constant-condition or empty-block
+    #[arg(long, value_name = "RULE", value_parser = parse_rule)]
+    enable: Vec<Rule>,
+    /// Turn off a rule
+    #[arg(long, value_name = "RULE", value_parser = parse_rule)]
+    disable: Vec<Rule>,
+    /// Files to lint; with none, or `-`, lints stdin
+    files: Vec<String>,
+}
+
+fn parse_rule(name: &str) -> Result<Rule, String> {
+    Rule::from_name(name).ok_or_else(|| format!("unknown rule '{}'", name))
+}
+
+#[derive(Args)]
+struct RunArgs {
+    /// Run CODE instead of a script file
+    #[arg(short, long = "eval", value_name = "CODE")]
+    eval: Option<String>,
+    /// Print the tokens scanned from the script instead of running it
+    #[arg(long)]
+    dump_tokens: bool,
+    /// Print the syntax tree parsed from the script instead of running it
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "sexpr"
+    )]
+    dump_ast: Option<AstFormat>,
+    /// Check that the script parses and defines every name it uses, without
+    /// running it
+    #[arg(long)]
+    check: bool,
+    /// Script file to run, or `-` to read it from stdin
+    script: Option<String>,
+    /// Arguments passed to the script as `args`
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum AstFormat {
+    /// Indented S-expressions
+    Sexpr,
+    /// A JSON array of statement nodes
+    Json,
+}
 
 struct Prompt {
-    had_error: bool,
-    interpreter: Interpreter,
+    engine: Engine,
 }
 
 impl Prompt {
     pub fn new() -> Self {
-        Self {
-            had_error: false,
-            interpreter: Interpreter::default(),
+        let mut engine = Engine::new();
+        engine.set_reporter(StderrReporter);
+        // OWNPY_PATH lists extra directories to search for imported modules.
+        if let Some(search_path) = env::var_os("OWNPY_PATH") {
+            for dir in env::split_paths(&search_path) {
+                engine.interpreter().add_search_path(dir);
+            }
         }
+        Self { engine }
     }
-    pub fn main(&mut self) {
-        let args: Vec<String> = env::args().collect();
-        let args_len: u8 = args.len() as u8;
-        if args_len > 2 {
-            println!("Usage: ownpy [script]")
-        } else if args_len == 2 {
-            self.run_file(&args[1]);
-        } else {
-            self.run_prompt();
+    pub fn main(&mut self) -> i32 {
+        let cli = match Cli::try_parse() {
+            Ok(cli) => cli,
+            Err(error) => {
+                let _ = error.print();
+                return match error.kind() {
+                    ErrorKind::DisplayHelp | ErrorKind::DisplayVersion => EX_OK,
+                    _ => EX_USAGE,
+                };
+            }
+        };
+        match cli.command {
+            Some(Command::Run(options)) => self.run_script(options),
+            Some(Command::Fmt(options)) => format_files(options),
+            Some(Command::Lint(options)) => lint_files(options),
+            Some(Command::Lsp) => match lsp::serve(io::stdin().lock(), io::stdout().lock()) {
+                Ok(code) => code,
+                Err(error) => {
+                    eprintln!("ownpy: language server stopped: {}", error);
+                    EX_IOERR
+                }
+            },
+            None if cli.run.eval.is_none() && cli.run.script.is_none() => {
+                if cli.run.dump_tokens || cli.run.dump_ast.is_some() || cli.run.check {
+                    eprintln!("ownpy: no script given");
+                    return EX_USAGE;
+                }
+                self.run_prompt();
+                EX_OK
+            }
+            None => self.run_script(cli.run),
         }
     }
-    fn run_file(&mut self, path: &String) {
-        let p: &Path = Path::new(path);
-        let source: String;
-        if !p.exists() {
-            panic!("FileNotFoundError")
+    fn run_script(&mut self, options: RunArgs) -> i32 {
+        let (source, args) = match (&options.eval, options.script.as_deref()) {
+            // With -e, every positional argument belongs to the script.
+            (Some(code), script) => {
+                let args = script
+                    .map(str::to_string)
+                    .into_iter()
+                    .chain(options.args.clone());
+                (code.clone(), args.collect())
+            }
+            (None, Some(script)) => match self.read_script(script) {
+                Ok(source) => (source, options.args.clone()),
+                Err(code) => return code,
+            },
+            (None, None) => {
+                eprintln!("ownpy: no script given");
+                return EX_USAGE;
+            }
+        };
+        self.engine.interpreter().set_global("args", args);
+        if options.dump_tokens || options.dump_ast.is_some() || options.check {
+            return self.inspect(&source, &options);
         }
-        match read_to_string(p) {
-            Ok(res) => source = res,
-            Err(_) => panic!("Failed to read content"),
+        self.run(&source)
+    }
+    fn read_script(&mut self, script: &str) -> Result<String, i32> {
+        if script == "-" {
+            let mut source = String::new();
+            return match io::stdin().read_to_string(&mut source) {
+                Ok(_) => Ok(source),
+                Err(error) => {
+                    eprintln!("ownpy: cannot read stdin: {}", error);
+                    Err(EX_IOERR)
+                }
+            };
         }
-        self.run(&source);
-        if self.had_error {
-            panic!("Failed to run")
+        let path = Path::new(script);
+        self.engine.interpreter().set_script_path(path);
+        read_to_string(path).map_err(|error| {
+            eprintln!("ownpy: cannot open '{}': {}", script, error);
+            EX_NOINPUT
+        })
+    }
+    // Handles --dump-tokens, --dump-ast and --check, none of which run the script.
+    fn inspect(&mut self, source: &str, options: &RunArgs) -> i32 {
+        let tokens = match Scanner::new(source.to_string()).scan_tokens() {
+            Ok(tokens) => tokens,
+            Err(errors) => {
+                eprintln!("{}", Error::Scan(errors));
+                return EX_DATAERR;
+            }
+        };
+        // Dumps are often piped into other tools; a closed pipe just ends them.
+        let mut out = io::stdout().lock();
+        if options.dump_tokens {
+            for token in &tokens {
+                if writeln!(out, "{:>4}  {}", token.line, token).is_err() {
+                    return EX_OK;
+                }
+            }
         }
+        if options.dump_ast.is_some() || options.check {
+            match Parser::new(tokens).parse() {
+                Ok(statements) => {
+                    if options.check {
+                        let undefined = self.undefined_names(&statements);
+                        if !undefined.is_empty() {
+                            eprintln!("{}", Error::Parse(undefined));
+                            return EX_DATAERR;
+                        }
+                    }
+                    let dump = match options.dump_ast {
+                        Some(AstFormat::Sexpr) => printer::sexpr(&statements),
+                        Some(AstFormat::Json) => printer::json(&statements),
+                        None => String::new(),
+                    };
+                    let _ = out.write_all(dump.as_bytes());
+                }
+                Err(errors) => {
+                    eprintln!("{}", Error::Parse(errors));
+                    return EX_DATAERR;
+                }
+            }
+        }
+        EX_OK
+    }
+    // Names the script reads or assigns that neither it nor the interpreter
+    // declares; running the script would fail on each of them.
+    fn undefined_names(&mut self, statements: &[Statement]) -> Vec<ParserError> {
+        let builtins: HashSet<String> = self
+            .engine
+            .interpreter()
+            .bindings()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        let resolution = resolver::resolve(statements);
+        let undefined = resolution
+            .unresolved
+            .into_iter()
+            .filter(|token| !builtins.contains(&token.lexeme));
+        undefined
+            .map(|token| ParserError {
+                message: format!("Undefined variable '{}'.", token.lexeme),
+                token: Box::new(token),
+            })
+            .collect()
     }
     fn run_prompt(&mut self) {
-        for line in io::stdin().lines() {
-            match line {
-                Ok(line_string) => {
-                    if line_string.is_empty() {
-                        break;
-                    }
-                    self.run(&line_string);
-                    self.had_error = false
+        repl::run(&mut self.engine);
+    }
+
+    // Errors were already reported on stderr; only the exit code is left.
+    fn run(&mut self, source: &str) -> i32 {
+        match self.engine.eval(source) {
+            Ok(_) => EX_OK,
+            Err(Error::Exit(code)) => code,
+            Err(Error::Runtime(_)) => EX_SOFTWARE,
+            Err(Error::Scan(_) | Error::Parse(_)) => EX_DATAERR,
+        }
+    }
+}
+fn format_files(options: FmtArgs) -> i32 {
+    if options.files.is_empty() || options.files == ["-"] {
+        let mut source = String::new();
+        if let Err(error) = io::stdin().read_to_string(&mut source) {
+            eprintln!("ownpy: cannot read stdin: {}", error);
+            return EX_IOERR;
+        }
+        return match formatter::format(&source) {
+            Ok(formatted) if options.check && formatted != source => {
+                println!("<stdin>");
+                EX_UNFORMATTED
+            }
+            Ok(_) if options.check => EX_OK,
+            Ok(formatted) => {
+                let _ = io::stdout().write_all(formatted.as_bytes());
+                EX_OK
+            }
+            Err(error) => {
+                eprintln!("{}", error);
+                EX_DATAERR
+            }
+        };
+    }
+    let mut status = EX_OK;
+    for file in &options.files {
+        let source = match read_to_string(file) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("ownpy: cannot open '{}': {}", file, error);
+                status = EX_NOINPUT;
+                continue;
+            }
+        };
+        let formatted = match formatter::format(&source) {
+            Ok(formatted) => formatted,
+            Err(error) => {
+                for line in error.to_string().lines() {
+                    eprintln!("{}: {}", file, line);
                 }
-                Err(_) => panic!("Failed to run prompt"),
+                status = EX_DATAERR;
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        if options.check {
+            println!("{}", file);
+            if status == EX_OK {
+                status = EX_UNFORMATTED;
             }
+        } else if let Err(error) = fs::write(file, formatted) {
+            eprintln!("ownpy: cannot write '{}': {}", file, error);
+            status = EX_IOERR;
         }
     }
+    status
+}
 
-    fn run(&mut self, source_ref: &String) {
-        let mut scanner: scanner::Scanner = scanner::Scanner::new(source_ref.to_string());
-        let tokens: Vec<common::Token> = scanner.scan_tokens();
-        let mut parser: parser::Parser = parser::Parser::new(tokens);
-        let statements: Vec<common::Statement> = parser.parse();
-        self.interpreter.interpret(statements.clone());
+fn lint_files(options: LintArgs) -> i32 {
+    let mut linter = Linter::default();
+    for &rule in &options.enable {
+        linter.enable(rule);
     }
-    fn error(&self, line: u8, message: &str) {
-        self.report(line, "", message);
+    for &rule in &options.disable {
+        linter.disable(rule);
     }
-    fn report(&self, line: u8, loc: &str, message: &str) {
-        println!("[line {0}] Error {1}: {2}", line, loc, message)
+    let stdin = options.files.is_empty() || options.files == ["-"];
+    let files = match stdin {
+        true => vec!["<stdin>".to_string()],
+        false => options.files,
+    };
+    let mut status = EX_OK;
+    for file in &files {
+        let source = match stdin {
+            true => {
+                let mut source = String::new();
+                if let Err(error) = io::stdin().read_to_string(&mut source) {
+                    eprintln!("ownpy: cannot read stdin: {}", error);
+                    return EX_IOERR;
+                }
+                source
+            }
+            false => match read_to_string(file) {
+                Ok(source) => source,
+                Err(error) => {
+                    eprintln!("ownpy: cannot open '{}': {}", file, error);
+                    status = EX_NOINPUT;
+                    continue;
+                }
+            },
+        };
+        match linter.lint(&source) {
+            Ok(diagnostics) => {
+                for diagnostic in &diagnostics {
+                    println!("{}: {}", file, diagnostic);
+                }
+                if !diagnostics.is_empty() && status == EX_OK {
+                    status = EX_WARNINGS;
+                }
+            }
+            Err(error) => {
+                for line in error.to_string().lines() {
+                    eprintln!("{}: {}", file, line);
+                }
+                status = EX_DATAERR;
+            }
+        }
     }
+    status
 }
+
 fn main() {
     let mut prompt: Prompt = Prompt::new();
-    prompt.main();
-    // let expr = Box::new(Expr::Binary {
-    //     left: Box::new(Expr::Unary {
-    //         operator: Token::new(TokenType::Minus, "-".to_string(), LiteralValue::None, 1),
-    //         right: Box::new(Expr::Literal {
-    //             value: LiteralValue::Integer(123),
-    //         }),
-    //     }),
-    //     operator: Token::new(TokenType::Star, "*".to_string(), LiteralValue::None, 1),
-    //     right: Box::new(Expr::Grouping {
-    //         expression: Box::new(Expr::Literal {
-    //             value: LiteralValue::Float(45.67),
-    //         }),
-    //     }),
-    // });
-    // let mut ast_str = String::new();
-    // ast_print(&mut ast_str, expr);
-    // println!("{}", ast_str);
+    process::exit(prompt.main());
 }
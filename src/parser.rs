@@ -1,14 +1,30 @@
-use crate::common::{Expr, LiteralValue, Statement, Token, TokenType};
+use crate::common::{Expr, LiteralValue, Param, Statement, Token, TokenType};
 use crate::error::ParserError;
+use crate::interpreter::{RED_ZONE, STACK_SEGMENT};
+
+// Deeper source is rejected rather than overflowing the native stack, both
+// here and in everything that walks the tree afterwards.
+const MAX_NESTING_DEPTH: usize = 256;
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: i128,
+    loop_depth: usize,
+    function_depth: usize,
+    nesting_depth: usize,
+    errors: Vec<ParserError>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Parser { tokens, current: 0 }
+        Parser {
+            tokens,
+            current: 0,
+            loop_depth: 0,
+            function_depth: 0,
+            nesting_depth: 0,
+            errors: Vec::new(),
+        }
     }
     fn match_type(&mut self, token_types: Vec<TokenType>) -> bool {
         for tt in token_types {
@@ -17,253 +33,307 @@ impl Parser {
                 return true;
             }
         }
-        return false;
+        false
     }
     fn check_type(&self, token_type: TokenType) -> bool {
         if self.is_at_end() {
             return false;
         }
-        return self.peek().token_type == token_type;
+        self.peek().token_type == token_type
     }
 
     fn advance(&mut self) -> Token {
         if !self.is_at_end() {
             self.current += 1;
         }
-        return self.previous();
+        self.previous()
     }
 
     fn previous(&self) -> Token {
-        return self
-            .tokens
+        self.tokens
             .get((self.current - 1) as usize)
             .unwrap()
-            .clone();
+            .clone()
     }
 
     fn is_at_end(&self) -> bool {
-        return self.peek().token_type == TokenType::EOF;
+        self.peek().token_type == TokenType::Eof
     }
     fn peek(&self) -> &Token {
-        return self.tokens.get(self.current as usize).unwrap();
+        self.tokens.get(self.current as usize).unwrap()
+    }
+    fn peek_next_type(&self) -> Option<&TokenType> {
+        self.tokens
+            .get((self.current + 1) as usize)
+            .map(|token| &token.token_type)
+    }
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParserError>,
+    ) -> Result<T, ParserError> {
+        if self.nesting_depth >= MAX_NESTING_DEPTH {
+            return Err(ParserError {
+                token: Box::new(self.peek().clone()),
+                message: "Too much nesting.".to_string(),
+            });
+        }
+        self.nesting_depth += 1;
+        let result = stacker::maybe_grow(RED_ZONE, STACK_SEGMENT, || parse(self));
+        self.nesting_depth -= 1;
+        result
     }
     fn expression(&mut self) -> Result<Box<Expr>, ParserError> {
-        return self.assignment();
+        self.nested(Self::assignment)
     }
     fn assignment(&mut self) -> Result<Box<Expr>, ParserError> {
-        let expr = *self.equality().unwrap();
+        let expr = *self.or()?;
         if self.match_type(vec![TokenType::Equal]) {
             let operator: Token = self.previous();
-            let value = self.assignment().unwrap();
+            let value = self.nested(Self::assignment)?;
             if let Expr::Var { name } = expr {
                 return Ok(Box::new(Expr::Assign { name, value }));
             } else {
-                self.error(operator, "Invalid assignment target");
+                self.error(operator, "Invalid assignment target.");
             }
         }
         Ok(Box::new(expr))
     }
+    fn or(&mut self) -> Result<Box<Expr>, ParserError> {
+        let mut expr = self.and()?;
+        while self.match_type(vec![TokenType::Or]) {
+            let operator = self.previous();
+            let right = self.and()?;
+            expr = Box::new(Expr::Logical {
+                left: expr,
+                operator,
+                right,
+            });
+        }
+        Ok(expr)
+    }
+    fn and(&mut self) -> Result<Box<Expr>, ParserError> {
+        let mut expr = self.equality()?;
+        while self.match_type(vec![TokenType::And]) {
+            let operator = self.previous();
+            let right = self.equality()?;
+            expr = Box::new(Expr::Logical {
+                left: expr,
+                operator,
+                right,
+            });
+        }
+        Ok(expr)
+    }
     fn equality(&mut self) -> Result<Box<Expr>, ParserError> {
-        match self.comparison() {
-            Ok(mut expr) => {
-                while self.match_type(vec![TokenType::BangEqual, TokenType::EqualEqual]) {
-                    let operator = self.previous();
-                    match self.comparison() {
-                        Ok(right) => {
-                            expr = Box::new(Expr::Binary {
-                                left: expr,
-                                operator,
-                                right,
-                            });
-                        }
-                        Err(error) => {
-                            return Err(error);
-                        }
-                    }
-                }
-                return Ok(expr);
-            }
-            Err(error) => {
-                return Err(error);
-            }
+        let mut expr = self.comparison()?;
+        while self.match_type(vec![TokenType::BangEqual, TokenType::EqualEqual]) {
+            let operator = self.previous();
+            let right = self.comparison()?;
+            expr = Box::new(Expr::Binary {
+                left: expr,
+                operator,
+                right,
+            });
         }
+        Ok(expr)
     }
     fn comparison(&mut self) -> Result<Box<Expr>, ParserError> {
-        match self.bitwise() {
-            Ok(mut expr) => {
-                while self.match_type(vec![
-                    TokenType::Greater,
-                    TokenType::GreaterEqual,
-                    TokenType::Less,
-                    TokenType::LessEqual,
-                ]) {
-                    let operator = self.previous();
-                    match self.bitwise() {
-                        Ok(right) => {
-                            expr = Box::new(Expr::Binary {
-                                left: expr,
-                                operator,
-                                right,
-                            });
-                        }
-                        Err(error) => {
-                            return Err(error);
-                        }
-                    }
-                }
-                return Ok(expr);
-            }
-            Err(error) => {
-                return Err(error);
-            }
+        let mut expr = self.bitwise()?;
+        while self.match_type(vec![
+            TokenType::Greater,
+            TokenType::GreaterEqual,
+            TokenType::Less,
+            TokenType::LessEqual,
+        ]) {
+            let operator = self.previous();
+            let right = self.bitwise()?;
+            expr = Box::new(Expr::Binary {
+                left: expr,
+                operator,
+                right,
+            });
         }
+        Ok(expr)
     }
     fn bitwise(&mut self) -> Result<Box<Expr>, ParserError> {
-        match self.term() {
-            Ok(mut expr) => {
-                while self.match_type(vec![TokenType::BitwiseAnd, TokenType::BitwiseOr]) {
-                    let operator = self.previous();
-                    match self.term() {
-                        Ok(right) => {
-                            expr = Box::new(Expr::Binary {
-                                left: expr,
-                                operator,
-                                right,
-                            });
-                        }
-                        Err(error) => {
-                            return Err(error);
-                        }
-                    }
-                }
-                return Ok(expr);
-            }
-            Err(error) => {
-                return Err(error);
-            }
+        let mut expr = self.term()?;
+        while self.match_type(vec![TokenType::BitwiseAnd, TokenType::BitwiseOr]) {
+            let operator = self.previous();
+            let right = self.term()?;
+            expr = Box::new(Expr::Binary {
+                left: expr,
+                operator,
+                right,
+            });
         }
+        Ok(expr)
     }
     fn term(&mut self) -> Result<Box<Expr>, ParserError> {
-        match self.factor() {
-            Ok(mut expr) => {
-                while self.match_type(vec![TokenType::Minus, TokenType::Plus]) {
-                    let operator = self.previous();
-                    match self.factor() {
-                        Ok(right) => {
-                            expr = Box::new(Expr::Binary {
-                                left: expr,
-                                operator,
-                                right,
-                            });
-                        }
-                        Err(error) => {
-                            return Err(error);
-                        }
-                    }
-                }
-                return Ok(expr);
-            }
-            Err(error) => {
-                return Err(error);
-            }
+        let mut expr = self.factor()?;
+        while self.match_type(vec![TokenType::Minus, TokenType::Plus]) {
+            let operator = self.previous();
+            let right = self.factor()?;
+            expr = Box::new(Expr::Binary {
+                left: expr,
+                operator,
+                right,
+            });
         }
+        Ok(expr)
     }
     fn factor(&mut self) -> Result<Box<Expr>, ParserError> {
-        match self.unary() {
-            Ok(mut expr) => {
-                while self.match_type(vec![TokenType::Slash, TokenType::Star]) {
-                    let operator = self.previous();
-                    match self.unary() {
-                        Ok(right) => {
-                            expr = Box::new(Expr::Binary {
-                                left: expr,
-                                operator,
-                                right,
-                            });
-                        }
-                        Err(error) => {
-                            return Err(error);
-                        }
-                    }
-                }
-                return Ok(expr);
-            }
-            Err(error) => {
-                return Err(error);
-            }
+        let mut expr = self.unary()?;
+        while self.match_type(vec![TokenType::Slash, TokenType::Star]) {
+            let operator = self.previous();
+            let right = self.unary()?;
+            expr = Box::new(Expr::Binary {
+                left: expr,
+                operator,
+                right,
+            });
         }
+        Ok(expr)
     }
     fn unary(&mut self) -> Result<Box<Expr>, ParserError> {
         if self.match_type(vec![TokenType::Bang, TokenType::Minus]) {
             let operator = self.previous();
-            match self.unary() {
-                Ok(right) => {
-                    return Ok(Box::new(Expr::Unary { operator, right }));
+            let right = self.nested(Self::unary)?;
+            return Ok(Box::new(Expr::Unary { operator, right }));
+        }
+        self.call()
+    }
+    fn call(&mut self) -> Result<Box<Expr>, ParserError> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.match_type(vec![TokenType::LeftParen]) {
+                expr = self.finish_call(expr)?;
+            } else if self.match_type(vec![TokenType::Dot]) {
+                let name =
+                    self.consume(TokenType::Identifier, "Expect property name after '.'.")?;
+                expr = Box::new(Expr::Get { object: expr, name });
+            } else if self.match_type(vec![TokenType::LeftBracket]) {
+                expr = self.finish_index(expr)?;
+            } else {
+                break;
+            }
+        }
+        Ok(expr)
+    }
+    // Parses `[index]`, or a slice `[start:end]` where either bound may be left out.
+    fn finish_index(&mut self, object: Box<Expr>) -> Result<Box<Expr>, ParserError> {
+        let bracket = self.previous();
+        let mut start = None;
+        if !self.check_type(TokenType::Colon) {
+            let index = self.expression()?;
+            if self.match_type(vec![TokenType::RightBracket]) {
+                return Ok(Box::new(Expr::Index {
+                    object,
+                    bracket,
+                    index,
+                }));
+            }
+            start = Some(index);
+        }
+        self.consume(TokenType::Colon, "Expect ']' after index.")?;
+        let end = match self.check_type(TokenType::RightBracket) {
+            true => None,
+            false => Some(self.expression()?),
+        };
+        self.consume(TokenType::RightBracket, "Expect ']' after slice.")?;
+        Ok(Box::new(Expr::Slice {
+            object,
+            bracket,
+            start,
+            end,
+        }))
+    }
+    fn finish_call(&mut self, callee: Box<Expr>) -> Result<Box<Expr>, ParserError> {
+        let mut arguments = Vec::new();
+        let mut keyword_arguments = Vec::new();
+        if !self.check_type(TokenType::RightParen) {
+            loop {
+                if self.check_type(TokenType::Identifier)
+                    && self.peek_next_type() == Some(&TokenType::Colon)
+                {
+                    let keyword = self.advance();
+                    self.advance();
+                    keyword_arguments.push((keyword, *self.expression()?));
+                } else if !keyword_arguments.is_empty() {
+                    return Err(ParserError {
+                        token: Box::new(self.peek().clone()),
+                        message: "Positional argument can't follow keyword arguments.".to_string(),
+                    });
+                } else {
+                    arguments.push(*self.expression()?);
                 }
-                Err(error) => {
-                    return Err(error);
+                if !self.match_type(vec![TokenType::Comma]) {
+                    break;
                 }
             }
         }
-        return self.primary();
+        let paren = self.consume(TokenType::RightParen, "Expect ')' after arguments.")?;
+        Ok(Box::new(Expr::Call {
+            callee,
+            paren,
+            arguments,
+            keyword_arguments,
+        }))
     }
     fn primary(&mut self) -> Result<Box<Expr>, ParserError> {
         if self.match_type(vec![TokenType::Number, TokenType::String]) {
-            return Ok(Box::new(Expr::Literal {
+            Ok(Box::new(Expr::Literal {
                 value: self.previous().literal_value,
-            }));
+            }))
         } else if self.match_type(vec![TokenType::True]) {
-            return Ok(Box::new(Expr::Literal {
+            Ok(Box::new(Expr::Literal {
                 value: LiteralValue::Bool(true),
-            }));
+            }))
         } else if self.match_type(vec![TokenType::False]) {
-            return Ok(Box::new(Expr::Literal {
+            Ok(Box::new(Expr::Literal {
                 value: LiteralValue::Bool(false),
-            }));
+            }))
         } else if self.match_type(vec![TokenType::Nil]) {
-            return Ok(Box::new(Expr::Literal {
+            Ok(Box::new(Expr::Literal {
                 value: LiteralValue::None,
-            }));
+            }))
         } else if self.match_type(vec![TokenType::LeftParen]) {
-            let expr = self.expression().unwrap();
-            if let Err(error) = self.consume(TokenType::RightParen, "Expect ')' after expression.")
-            {
-                return Err(error);
-            }
-            return Ok(Box::new(Expr::Grouping { expression: expr }));
+            let expr = self.expression()?;
+            self.consume(TokenType::RightParen, "Expect ')' after expression.")?;
+            Ok(Box::new(Expr::Grouping { expression: expr }))
         } else if self.match_type(vec![TokenType::Identifier]) {
             let previous_val = self.previous();
-            return Ok(Box::new(Expr::Var { name: previous_val }));
+            Ok(Box::new(Expr::Var { name: previous_val }))
+        } else if self.match_type(vec![TokenType::Fun]) {
+            self.consume(TokenType::LeftParen, "Expect '(' after 'fun'.")?;
+            let params = self.parameters(TokenType::RightParen)?;
+            let body = self.function_body("lambda")?;
+            Ok(Box::new(Expr::Lambda { params, body }))
+        } else if self.match_type(vec![TokenType::BitwiseOr]) {
+            let params = self.parameters(TokenType::BitwiseOr)?;
+            let body = self.arrow_body()?;
+            Ok(Box::new(Expr::Lambda { params, body }))
         } else {
-            let message = " Expect expression.";
-            return Err(ParserError {
-                token: self.peek().clone(),
+            let message = "Expect expression.";
+            Err(ParserError {
+                token: Box::new(self.peek().clone()),
                 message: message.to_string(),
-            });
+            })
         }
     }
     fn consume(&mut self, token_type: TokenType, message: &str) -> Result<Token, ParserError> {
         if self.check_type(token_type) {
             return Ok(self.advance());
         }
-        return Err(ParserError {
-            token: self.peek().clone(),
+        Err(ParserError {
+            token: Box::new(self.peek().clone()),
             message: message.to_string(),
-        });
-    }
-    fn error(&self, token: Token, message: &str) {
-        if token.token_type == TokenType::EOF {
-            self.report(token.line, " at end", message);
-        } else {
-            let mut new_message = String::new();
-            new_message.push_str(token.lexeme.as_str());
-            new_message.push_str(message.clone());
-            self.report(token.line, " at '", new_message.as_str());
-        }
+        })
     }
-
-    fn report(&self, line: u8, loc: &str, message: &str) {
-        println!("[line {0}:{1}] Error: {2}", line, loc, message)
+    fn error(&mut self, token: Token, message: &str) {
+        self.errors.push(ParserError {
+            token: Box::new(token),
+            message: message.to_string(),
+        });
     }
     fn synchronize(&mut self) {
         self.advance();
@@ -271,95 +341,353 @@ impl Parser {
             if self.previous().token_type == TokenType::Semicolon {
                 return;
             }
-        }
-        match self.peek().token_type {
-            TokenType::Class
-            | TokenType::Fun
-            | TokenType::Var
-            | TokenType::For
-            | TokenType::If
-            | TokenType::While
-            | TokenType::Print
-            | TokenType::Return => {
-                return;
-            }
-            _ => {
-                self.advance();
+            match self.peek().token_type {
+                TokenType::Class
+                | TokenType::Fun
+                | TokenType::Var
+                | TokenType::For
+                | TokenType::If
+                | TokenType::While
+                | TokenType::Print
+                | TokenType::Return
+                | TokenType::Throw
+                | TokenType::Try => {
+                    return;
+                }
+                _ => {
+                    self.advance();
+                }
             }
         }
     }
     fn print_statement(&mut self) -> Result<Statement, ParserError> {
-        match self.expression() {
-            Ok(expr) => {
-                if let Err(err) =
-                    self.consume(TokenType::Semicolon, "Expected ';' after print statement")
-                {
-                    return Err(err);
-                }
-                Ok(Statement::Print { expr: *expr })
-            }
-            Err(err) => Err(err),
-        }
+        let keyword = self.previous();
+        let expr = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expected ';' after print statement")?;
+        Ok(Statement::Print {
+            keyword,
+            expr: *expr,
+        })
     }
     fn expression_statement(&mut self) -> Result<Statement, ParserError> {
-        match self.expression() {
-            Ok(expr) => {
-                if let Err(err) =
-                    self.consume(TokenType::Semicolon, "Expected ';' after print statement")
-                {
-                    return Err(err);
-                }
-                Ok(Statement::Expr { expr: *expr })
-            }
-            Err(err) => Err(err),
+        let expr = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expected ';' after print statement")?;
+        Ok(Statement::Expr { expr: *expr })
+    }
+    fn if_statement(&mut self) -> Result<Statement, ParserError> {
+        let keyword = self.previous();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'if'.")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after if condition.")?;
+        let then_branch = Box::new(self.statement()?);
+        let mut else_branch = None;
+        if self.match_type(vec![TokenType::Else]) {
+            else_branch = Some(Box::new(self.statement()?));
+        }
+        Ok(Statement::If {
+            keyword,
+            condition: *condition,
+            then_branch,
+            else_branch,
+        })
+    }
+    fn while_statement(&mut self) -> Result<Statement, ParserError> {
+        let keyword = self.previous();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'while'.")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after condition.")?;
+        let body = Box::new(self.loop_body()?);
+        Ok(Statement::While {
+            keyword,
+            condition: *condition,
+            body,
+        })
+    }
+    fn for_statement(&mut self) -> Result<Statement, ParserError> {
+        let keyword = self.previous();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
+        let initializer = if self.match_type(vec![TokenType::Semicolon]) {
+            None
+        } else if self.match_type(vec![TokenType::Var]) {
+            Some(Box::new(self.var_declaration()?))
+        } else {
+            Some(Box::new(self.expression_statement()?))
+        };
+        let mut condition = None;
+        if !self.check_type(TokenType::Semicolon) {
+            condition = Some(*self.expression()?);
+        }
+        self.consume(TokenType::Semicolon, "Expect ';' after loop condition.")?;
+        let mut increment = None;
+        if !self.check_type(TokenType::RightParen) {
+            increment = Some(*self.expression()?);
+        }
+        self.consume(TokenType::RightParen, "Expect ')' after for clauses.")?;
+        let body = Box::new(self.loop_body()?);
+        Ok(Statement::For {
+            keyword,
+            initializer,
+            condition,
+            increment,
+            body,
+        })
+    }
+    fn loop_body(&mut self) -> Result<Statement, ParserError> {
+        self.loop_depth += 1;
+        let body = self.statement();
+        self.loop_depth -= 1;
+        body
+    }
+    fn loop_exit_statement(&mut self) -> Result<Statement, ParserError> {
+        let keyword = self.previous();
+        if self.loop_depth == 0 {
+            // The statement itself is well formed, so parsing carries on.
+            let message = format!("Can't use '{}' outside of a loop.", keyword.lexeme);
+            self.error(keyword.clone(), &message);
+        }
+        self.consume(
+            TokenType::Semicolon,
+            &format!("Expect ';' after '{}'.", keyword.lexeme),
+        )?;
+        match keyword.token_type {
+            TokenType::Break => Ok(Statement::Break { keyword }),
+            _ => Ok(Statement::Continue { keyword }),
         }
     }
 
     fn statement(&mut self) -> Result<Statement, ParserError> {
+        self.nested(Self::parse_statement)
+    }
+    fn parse_statement(&mut self) -> Result<Statement, ParserError> {
         if self.match_type(vec![TokenType::Print]) {
             return self.print_statement();
         } else if self.match_type(vec![TokenType::LeftBrace]) {
             return self.block_statement();
+        } else if self.match_type(vec![TokenType::If]) {
+            return self.if_statement();
+        } else if self.match_type(vec![TokenType::While]) {
+            return self.while_statement();
+        } else if self.match_type(vec![TokenType::For]) {
+            return self.for_statement();
+        } else if self.match_type(vec![TokenType::Break, TokenType::Continue]) {
+            return self.loop_exit_statement();
+        } else if self.match_type(vec![TokenType::Return]) {
+            return self.return_statement();
+        } else if self.match_type(vec![TokenType::Throw]) {
+            return self.throw_statement();
+        } else if self.match_type(vec![TokenType::Try]) {
+            return self.try_statement();
         }
         self.expression_statement()
     }
+    fn parameters(&mut self, closing: TokenType) -> Result<Vec<Param>, ParserError> {
+        let mut params: Vec<Param> = Vec::new();
+        if !self.check_type(closing.clone()) {
+            loop {
+                let is_rest = self.match_type(vec![TokenType::Ellipsis]);
+                let name = self.consume(TokenType::Identifier, "Expect parameter name.")?;
+                let mut default = None;
+                if !is_rest && self.match_type(vec![TokenType::Equal]) {
+                    // A '|' closes the parameter list of an arrow lambda, so its
+                    // defaults stop short of the bitwise operators.
+                    default = Some(*match closing {
+                        TokenType::BitwiseOr => self.term()?,
+                        _ => self.expression()?,
+                    });
+                } else if params.iter().any(|param| param.default.is_some()) && !is_rest {
+                    return Err(ParserError {
+                        token: Box::new(name),
+                        message: "Parameter without a default can't follow one with a default."
+                            .to_string(),
+                    });
+                }
+                params.push(Param {
+                    name,
+                    default,
+                    is_rest,
+                });
+                if is_rest || !self.match_type(vec![TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume(closing, "Expect end of parameter list.")?;
+        Ok(params)
+    }
+    fn function_body(&mut self, kind: &str) -> Result<Vec<Statement>, ParserError> {
+        self.consume(
+            TokenType::LeftBrace,
+            &format!("Expect '{{' before {kind} body."),
+        )?;
+        let enclosing_loop_depth = self.loop_depth;
+        self.loop_depth = 0;
+        self.function_depth += 1;
+        let body = self.block();
+        self.function_depth -= 1;
+        self.loop_depth = enclosing_loop_depth;
+        body
+    }
+    // The closing '|' stands in for the `return` keyword the body implies.
+    fn arrow_body(&mut self) -> Result<Vec<Statement>, ParserError> {
+        let keyword = self.previous();
+        let value = self.expression()?;
+        Ok(vec![Statement::Return {
+            keyword,
+            value: Some(*value),
+        }])
+    }
+    fn function_declaration(&mut self) -> Result<Statement, ParserError> {
+        let name = self.consume(TokenType::Identifier, "Expect function name.")?;
+        self.consume(TokenType::LeftParen, "Expect '(' after function name.")?;
+        let params = self.parameters(TokenType::RightParen)?;
+        let body = self.function_body("function")?;
+        Ok(Statement::Function { name, params, body })
+    }
+    fn return_statement(&mut self) -> Result<Statement, ParserError> {
+        let keyword = self.previous();
+        if self.function_depth == 0 {
+            return Err(ParserError {
+                token: Box::new(keyword),
+                message: "Can't return from top-level code.".to_string(),
+            });
+        }
+        let mut value = None;
+        if !self.check_type(TokenType::Semicolon) {
+            value = Some(*self.expression()?);
+        }
+        self.consume(TokenType::Semicolon, "Expect ';' after return value.")?;
+        Ok(Statement::Return { keyword, value })
+    }
+    fn throw_statement(&mut self) -> Result<Statement, ParserError> {
+        let keyword = self.previous();
+        let value = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expect ';' after thrown value.")?;
+        Ok(Statement::Throw {
+            keyword,
+            value: *value,
+        })
+    }
+    fn try_statement(&mut self) -> Result<Statement, ParserError> {
+        self.consume(TokenType::LeftBrace, "Expect '{' after 'try'.")?;
+        let body = self.block()?;
+        let mut catch_clause = None;
+        if self.match_type(vec![TokenType::Catch]) {
+            self.consume(TokenType::LeftParen, "Expect '(' after 'catch'.")?;
+            let name = self.consume(TokenType::Identifier, "Expect exception variable name.")?;
+            self.consume(
+                TokenType::RightParen,
+                "Expect ')' after exception variable.",
+            )?;
+            self.consume(TokenType::LeftBrace, "Expect '{' before catch body.")?;
+            catch_clause = Some((name, self.block()?));
+        }
+        let mut finally_body = None;
+        if self.match_type(vec![TokenType::Finally]) {
+            self.consume(TokenType::LeftBrace, "Expect '{' after 'finally'.")?;
+            finally_body = Some(self.block()?);
+        }
+        if catch_clause.is_none() && finally_body.is_none() {
+            return Err(ParserError {
+                token: Box::new(self.peek().clone()),
+                message: "Expect 'catch' or 'finally' after try block.".to_string(),
+            });
+        }
+        Ok(Statement::Try {
+            body,
+            catch_clause,
+            finally_body,
+        })
+    }
     fn var_declaration(&mut self) -> Result<Statement, ParserError> {
-        let name = self
-            .consume(TokenType::Identifier, "Expected variable name")
-            .unwrap();
+        let name = self.consume(TokenType::Identifier, "Expected variable name")?;
         let mut initialiser: Box<Expr> = Box::new(Expr::Literal {
             value: LiteralValue::None,
         });
         if self.match_type(vec![TokenType::Equal]) {
-            initialiser = self.expression().unwrap();
+            initialiser = self.expression()?;
         }
-        let _ = self.consume(
+        self.consume(
             TokenType::Semicolon,
             "Expect ';' after variable declaration",
-        );
-        return Ok(Statement::Var {
+        )?;
+        Ok(Statement::Var {
             name,
             expr: *initialiser,
-        });
+        })
+    }
+    fn module_name(&mut self) -> Result<Token, ParserError> {
+        if self.match_type(vec![TokenType::String, TokenType::Identifier]) {
+            return Ok(self.previous());
+        }
+        Err(ParserError {
+            token: Box::new(self.peek().clone()),
+            message: "Expect module name or path.".to_string(),
+        })
+    }
+    fn import_statement(&mut self) -> Result<Statement, ParserError> {
+        let keyword = self.previous();
+        let module = self.module_name()?;
+        let mut alias = None;
+        if self.match_type(vec![TokenType::As]) {
+            alias = Some(self.consume(TokenType::Identifier, "Expect name after 'as'.")?);
+        }
+        self.consume(TokenType::Semicolon, "Expect ';' after import.")?;
+        Ok(Statement::Import {
+            keyword,
+            module,
+            alias,
+        })
+    }
+    fn import_from_statement(&mut self) -> Result<Statement, ParserError> {
+        let keyword = self.previous();
+        let module = self.module_name()?;
+        self.consume(TokenType::Import, "Expect 'import' after module name.")?;
+        let mut names = vec![self.consume(TokenType::Identifier, "Expect name to import.")?];
+        while self.match_type(vec![TokenType::Comma]) {
+            names.push(self.consume(TokenType::Identifier, "Expect name to import.")?);
+        }
+        self.consume(TokenType::Semicolon, "Expect ';' after import.")?;
+        Ok(Statement::FromImport {
+            keyword,
+            module,
+            names,
+        })
     }
     fn declaration(&mut self) -> Result<Statement, ParserError> {
+        self.nested(Self::parse_declaration)
+    }
+    fn parse_declaration(&mut self) -> Result<Statement, ParserError> {
         if self.match_type(vec![TokenType::Var]) {
             return self.var_declaration();
         }
-        return self.statement();
+        if self.match_type(vec![TokenType::Import]) {
+            return self.import_statement();
+        }
+        if self.match_type(vec![TokenType::From]) {
+            return self.import_from_statement();
+        }
+        if self.check_type(TokenType::Fun) && self.peek_next_type() == Some(&TokenType::Identifier)
+        {
+            self.advance();
+            return self.function_declaration();
+        }
+        self.statement()
     }
-    fn block_statement(&mut self) -> Result<Statement, ParserError> {
+    fn block(&mut self) -> Result<Vec<Statement>, ParserError> {
         let mut stmt_vec = Vec::new();
-        while !self.match_type(vec![TokenType::RightBrace]) {
-            let new_stmt = self.declaration().unwrap();
-            stmt_vec.push(new_stmt);
+        while !self.check_type(TokenType::RightBrace) && !self.is_at_end() {
+            stmt_vec.push(self.declaration()?);
         }
-        let _ = self.consume(TokenType::RightBrace, "Expect '}' after block.");
+        self.consume(TokenType::RightBrace, "Expect '}' after block.")?;
+        Ok(stmt_vec)
+    }
+    fn block_statement(&mut self) -> Result<Statement, ParserError> {
         Ok(Statement::Block {
-            statements: stmt_vec,
+            brace: self.previous(),
+            statements: self.block()?,
         })
     }
-    pub fn parse(&mut self) -> Vec<Statement> {
+    pub fn parse(&mut self) -> Result<Vec<Statement>, Vec<ParserError>> {
         let mut statements: Vec<Statement> = vec![];
         while !self.is_at_end() {
             match self.declaration() {
@@ -367,16 +695,15 @@ impl Parser {
                     statements.push(stmt);
                 }
                 Err(error) => {
-                    self.error(error.token, &error.message);
+                    self.errors.push(error);
                     self.synchronize();
-                    return vec![Statement::Expr {
-                        expr: Expr::Literal {
-                            value: LiteralValue::None,
-                        },
-                    }];
                 }
             }
         }
-        statements
+        if self.errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
     }
 }
@@ -0,0 +1,688 @@
+use crate::common::{Expr, LiteralValue, Param, Statement, Token};
+use crate::stdlib::write_string;
+
+// Readable S-expressions, one top-level statement per line. Nested statements
+// start on their own line, indented two spaces per level.
+pub fn sexpr(statements: &[Statement]) -> String {
+    let mut out = String::new();
+    for statement in statements {
+        out.push_str(&statement_sexpr(statement, 0));
+        out.push('\n');
+    }
+    out
+}
+
+pub fn expr_sexpr(expr: &Expr) -> String {
+    expr_sexpr_at(expr, 0)
+}
+
+// A JSON array of statements. Every node is an object whose "type" names the
+// Expr or Statement variant; fields mirror the variant's fields.
+pub fn json(statements: &[Statement]) -> String {
+    let mut out = String::new();
+    write_json(&mut out, &statements_json(statements), 0);
+    out.push('\n');
+    out
+}
+
+fn statement_sexpr(statement: &Statement, depth: usize) -> String {
+    let nested = |statement: &Statement| {
+        format!(
+            "\n{}{}",
+            "  ".repeat(depth + 1),
+            statement_sexpr(statement, depth + 1)
+        )
+    };
+    let body = |statements: &[Statement]| statements.iter().map(nested).collect::<String>();
+    let expr = |expr: &Expr| expr_sexpr_at(expr, depth);
+    let optional = |value: &Option<Expr>| value.as_ref().map_or("nil".to_string(), expr);
+    match statement {
+        Statement::Expr { expr: value } => format!("(expr {})", expr(value)),
+        Statement::Print { expr: value, .. } => format!("(print {})", expr(value)),
+        Statement::Var { name, expr: value } => format!("(var {} {})", name.lexeme, expr(value)),
+        Statement::Block { statements, .. } => format!("(block{})", body(statements)),
+        Statement::If {
+            condition,
+            then_branch,
+            else_branch,
+            ..
+        } => format!(
+            "(if {}{}{})",
+            expr(condition),
+            nested(then_branch),
+            else_branch.as_deref().map_or(String::new(), nested)
+        ),
+        Statement::While {
+            condition, body, ..
+        } => {
+            format!("(while {}{})", expr(condition), nested(body))
+        }
+        Statement::For {
+            initializer,
+            condition,
+            increment,
+            body,
+            ..
+        } => format!(
+            "(for {} {} {}{})",
+            initializer
+                .as_deref()
+                .map_or("nil".to_string(), |initializer| statement_sexpr(
+                    initializer,
+                    depth
+                )),
+            optional(condition),
+            optional(increment),
+            nested(body)
+        ),
+        Statement::Break { .. } => "(break)".to_string(),
+        Statement::Continue { .. } => "(continue)".to_string(),
+        Statement::Function {
+            name,
+            params,
+            body: statements,
+        } => format!(
+            "(fun {} {}{})",
+            name.lexeme,
+            params_sexpr(params, depth),
+            body(statements)
+        ),
+        Statement::Return { value: None, .. } => "(return)".to_string(),
+        Statement::Return {
+            value: Some(value), ..
+        } => format!("(return {})", expr(value)),
+        Statement::Throw { value, .. } => format!("(throw {})", expr(value)),
+        Statement::Try {
+            body: statements,
+            catch_clause,
+            finally_body,
+        } => {
+            let indent = "  ".repeat(depth + 1);
+            let mut text = format!("(try{}", body(statements));
+            if let Some((name, statements)) = catch_clause {
+                let clause = statements
+                    .iter()
+                    .map(|statement| {
+                        format!("\n{}  {}", indent, statement_sexpr(statement, depth + 2))
+                    })
+                    .collect::<String>();
+                text.push_str(&format!("\n{}(catch {}{})", indent, name.lexeme, clause));
+            }
+            if let Some(statements) = finally_body {
+                let clause = statements
+                    .iter()
+                    .map(|statement| {
+                        format!("\n{}  {}", indent, statement_sexpr(statement, depth + 2))
+                    })
+                    .collect::<String>();
+                text.push_str(&format!("\n{}(finally{})", indent, clause));
+            }
+            text.push(')');
+            text
+        }
+        Statement::Import { module, alias, .. } => match alias {
+            Some(alias) => format!("(import {} {})", module.lexeme, alias.lexeme),
+            None => format!("(import {})", module.lexeme),
+        },
+        Statement::FromImport { module, names, .. } => {
+            let names = names.iter().map(|name| name.lexeme.as_str());
+            format!(
+                "(from {} {})",
+                module.lexeme,
+                names.collect::<Vec<_>>().join(" ")
+            )
+        }
+    }
+}
+
+fn expr_sexpr_at(expr: &Expr, depth: usize) -> String {
+    let sub = |expr: &Expr| expr_sexpr_at(expr, depth);
+    match expr {
+        Expr::Binary {
+            left,
+            operator,
+            right,
+        }
+        | Expr::Logical {
+            left,
+            operator,
+            right,
+        } => format!("({} {} {})", operator.lexeme, sub(left), sub(right)),
+        Expr::Grouping { expression } => format!("(group {})", sub(expression)),
+        Expr::Literal { value } => literal_sexpr(value),
+        Expr::Unary { operator, right } => format!("({} {})", operator.lexeme, sub(right)),
+        Expr::Var { name } => name.lexeme.clone(),
+        Expr::Assign { name, value } => format!("(= {} {})", name.lexeme, sub(value)),
+        Expr::Call {
+            callee,
+            arguments,
+            keyword_arguments,
+            ..
+        } => {
+            let mut text = format!("(call {}", sub(callee));
+            for argument in arguments {
+                text.push(' ');
+                text.push_str(&sub(argument));
+            }
+            for (name, value) in keyword_arguments {
+                text.push_str(&format!(" (: {} {})", name.lexeme, sub(value)));
+            }
+            text.push(')');
+            text
+        }
+        Expr::Lambda { params, body } => {
+            let statements = body.iter().map(|statement| {
+                format!(
+                    "\n{}{}",
+                    "  ".repeat(depth + 1),
+                    statement_sexpr(statement, depth + 1)
+                )
+            });
+            format!(
+                "(lambda {}{})",
+                params_sexpr(params, depth),
+                statements.collect::<String>()
+            )
+        }
+        Expr::Get { object, name } => format!("(. {} {})", sub(object), name.lexeme),
+        Expr::Index { object, index, .. } => format!("(index {} {})", sub(object), sub(index)),
+        Expr::Slice {
+            object, start, end, ..
+        } => {
+            let bound = |bound: &Option<Box<Expr>>| bound.as_deref().map_or("nil".to_string(), sub);
+            format!("(slice {} {} {})", sub(object), bound(start), bound(end))
+        }
+    }
+}
+
+fn params_sexpr(params: &[Param], depth: usize) -> String {
+    let params = params.iter().map(|param| match &param.default {
+        _ if param.is_rest => format!("(... {})", param.name.lexeme),
+        Some(default) => format!(
+            "(= {} {})",
+            param.name.lexeme,
+            expr_sexpr_at(default, depth)
+        ),
+        None => param.name.lexeme.clone(),
+    });
+    format!("({})", params.collect::<Vec<_>>().join(" "))
+}
+
+fn literal_sexpr(value: &LiteralValue) -> String {
+    match value {
+        LiteralValue::String(value) => {
+            let mut text = String::new();
+            write_string(&mut text, value);
+            text
+        }
+        // Debug formatting keeps the fraction, so 1.0 doesn't read back as an integer.
+        LiteralValue::Float(value) => format!("{:?}", value),
+        value => value.to_string(),
+    }
+}
+
+enum Json {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+fn node(kind: &str, fields: Vec<(&'static str, Json)>) -> Json {
+    let mut object = vec![("type", Json::String(kind.to_string()))];
+    object.extend(fields);
+    Json::Object(object)
+}
+
+fn name(token: &Token) -> Json {
+    Json::String(token.lexeme.clone())
+}
+
+fn line(token: &Token) -> Json {
+    Json::Integer(token.line as i64)
+}
+
+fn statements_json(statements: &[Statement]) -> Json {
+    Json::Array(statements.iter().map(statement_json).collect())
+}
+
+fn optional_json(expr: Option<&Expr>) -> Json {
+    expr.map_or(Json::Null, expr_json)
+}
+
+fn statement_json(statement: &Statement) -> Json {
+    match statement {
+        Statement::Expr { expr } => node("Expr", vec![("expr", expr_json(expr))]),
+        Statement::Print { keyword, expr } => node(
+            "Print",
+            vec![("expr", expr_json(expr)), ("line", line(keyword))],
+        ),
+        Statement::Var { name: var, expr } => node(
+            "Var",
+            vec![
+                ("name", name(var)),
+                ("expr", expr_json(expr)),
+                ("line", line(var)),
+            ],
+        ),
+        Statement::Block { brace, statements } => node(
+            "Block",
+            vec![
+                ("statements", statements_json(statements)),
+                ("line", line(brace)),
+            ],
+        ),
+        Statement::If {
+            keyword,
+            condition,
+            then_branch,
+            else_branch,
+        } => node(
+            "If",
+            vec![
+                ("condition", expr_json(condition)),
+                ("then_branch", statement_json(then_branch)),
+                (
+                    "else_branch",
+                    else_branch.as_deref().map_or(Json::Null, statement_json),
+                ),
+                ("line", line(keyword)),
+            ],
+        ),
+        Statement::While {
+            keyword,
+            condition,
+            body,
+        } => node(
+            "While",
+            vec![
+                ("condition", expr_json(condition)),
+                ("body", statement_json(body)),
+                ("line", line(keyword)),
+            ],
+        ),
+        Statement::For {
+            keyword,
+            initializer,
+            condition,
+            increment,
+            body,
+        } => node(
+            "For",
+            vec![
+                (
+                    "initializer",
+                    initializer.as_deref().map_or(Json::Null, statement_json),
+                ),
+                ("condition", optional_json(condition.as_ref())),
+                ("increment", optional_json(increment.as_ref())),
+                ("body", statement_json(body)),
+                ("line", line(keyword)),
+            ],
+        ),
+        Statement::Break { keyword } => node("Break", vec![("line", line(keyword))]),
+        Statement::Continue { keyword } => node("Continue", vec![("line", line(keyword))]),
+        Statement::Function {
+            name: function,
+            params,
+            body,
+        } => node(
+            "Function",
+            vec![
+                ("name", name(function)),
+                ("params", params_json(params)),
+                ("body", statements_json(body)),
+                ("line", line(function)),
+            ],
+        ),
+        Statement::Return { keyword, value } => node(
+            "Return",
+            vec![
+                ("value", optional_json(value.as_ref())),
+                ("line", line(keyword)),
+            ],
+        ),
+        Statement::Throw { keyword, value } => node(
+            "Throw",
+            vec![("value", expr_json(value)), ("line", line(keyword))],
+        ),
+        Statement::Try {
+            body,
+            catch_clause,
+            finally_body,
+        } => node(
+            "Try",
+            vec![
+                ("body", statements_json(body)),
+                (
+                    "catch_clause",
+                    catch_clause
+                        .as_ref()
+                        .map_or(Json::Null, |(variable, statements)| {
+                            Json::Object(vec![
+                                ("name", name(variable)),
+                                ("body", statements_json(statements)),
+                            ])
+                        }),
+                ),
+                (
+                    "finally_body",
+                    finally_body.as_deref().map_or(Json::Null, statements_json),
+                ),
+            ],
+        ),
+        Statement::Import {
+            keyword,
+            module,
+            alias,
+        } => node(
+            "Import",
+            vec![
+                ("module", module_json(module)),
+                ("alias", alias.as_ref().map_or(Json::Null, name)),
+                ("line", line(keyword)),
+            ],
+        ),
+        Statement::FromImport {
+            keyword,
+            module,
+            names,
+        } => node(
+            "FromImport",
+            vec![
+                ("module", module_json(module)),
+                ("names", Json::Array(names.iter().map(name).collect())),
+                ("line", line(keyword)),
+            ],
+        ),
+    }
+}
+
+// Paths keep their string value; bare module names are identifiers.
+fn module_json(module: &Token) -> Json {
+    match &module.literal_value {
+        LiteralValue::String(path) => Json::String(path.clone()),
+        _ => name(module),
+    }
+}
+
+fn params_json(params: &[Param]) -> Json {
+    let params = params.iter().map(|param| {
+        Json::Object(vec![
+            ("name", name(&param.name)),
+            ("default", optional_json(param.default.as_ref())),
+            ("is_rest", Json::Bool(param.is_rest)),
+        ])
+    });
+    Json::Array(params.collect())
+}
+
+fn expr_json(expr: &Expr) -> Json {
+    match expr {
+        Expr::Binary {
+            left,
+            operator,
+            right,
+        } => node(
+            "Binary",
+            vec![
+                ("left", expr_json(left)),
+                ("operator", name(operator)),
+                ("right", expr_json(right)),
+                ("line", line(operator)),
+            ],
+        ),
+        Expr::Grouping { expression } => {
+            node("Grouping", vec![("expression", expr_json(expression))])
+        }
+        Expr::Literal { value } => node("Literal", vec![("value", literal_json(value))]),
+        Expr::Unary { operator, right } => node(
+            "Unary",
+            vec![
+                ("operator", name(operator)),
+                ("right", expr_json(right)),
+                ("line", line(operator)),
+            ],
+        ),
+        Expr::Var { name: var } => node("Var", vec![("name", name(var)), ("line", line(var))]),
+        Expr::Assign { name: var, value } => node(
+            "Assign",
+            vec![
+                ("name", name(var)),
+                ("value", expr_json(value)),
+                ("line", line(var)),
+            ],
+        ),
+        Expr::Logical {
+            left,
+            operator,
+            right,
+        } => node(
+            "Logical",
+            vec![
+                ("left", expr_json(left)),
+                ("operator", name(operator)),
+                ("right", expr_json(right)),
+                ("line", line(operator)),
+            ],
+        ),
+        Expr::Call {
+            callee,
+            paren,
+            arguments,
+            keyword_arguments,
+        } => {
+            let keyword_arguments = keyword_arguments.iter().map(|(keyword, value)| {
+                Json::Object(vec![("name", name(keyword)), ("value", expr_json(value))])
+            });
+            node(
+                "Call",
+                vec![
+                    ("callee", expr_json(callee)),
+                    (
+                        "arguments",
+                        Json::Array(arguments.iter().map(expr_json).collect()),
+                    ),
+                    (
+                        "keyword_arguments",
+                        Json::Array(keyword_arguments.collect()),
+                    ),
+                    ("line", line(paren)),
+                ],
+            )
+        }
+        Expr::Lambda { params, body } => node(
+            "Lambda",
+            vec![
+                ("params", params_json(params)),
+                ("body", statements_json(body)),
+            ],
+        ),
+        Expr::Get { object, name: key } => node(
+            "Get",
+            vec![
+                ("object", expr_json(object)),
+                ("name", name(key)),
+                ("line", line(key)),
+            ],
+        ),
+        Expr::Index {
+            object,
+            bracket,
+            index,
+        } => node(
+            "Index",
+            vec![
+                ("object", expr_json(object)),
+                ("index", expr_json(index)),
+                ("line", line(bracket)),
+            ],
+        ),
+        Expr::Slice {
+            object,
+            bracket,
+            start,
+            end,
+        } => node(
+            "Slice",
+            vec![
+                ("object", expr_json(object)),
+                ("start", optional_json(start.as_deref())),
+                ("end", optional_json(end.as_deref())),
+                ("line", line(bracket)),
+            ],
+        ),
+    }
+}
+
+fn literal_json(value: &LiteralValue) -> Json {
+    match value {
+        LiteralValue::None => Json::Null,
+        LiteralValue::Bool(value) => Json::Bool(*value),
+        LiteralValue::Integer(value) => Json::Integer(*value),
+        LiteralValue::Float(value) => Json::Float(*value),
+        LiteralValue::String(value) => Json::String(value.clone()),
+        value => Json::String(value.to_string()),
+    }
+}
+
+fn write_json(out: &mut String, value: &Json, depth: usize) {
+    match value {
+        Json::Null => out.push_str("null"),
+        Json::Bool(value) => out.push_str(&value.to_string()),
+        Json::Integer(value) => out.push_str(&value.to_string()),
+        // JSON has no inf; an integer literal too large for f64 is the only way to get one.
+        Json::Float(value) if !value.is_finite() => out.push_str("null"),
+        Json::Float(value) => out.push_str(&format!("{:?}", value)),
+        Json::String(value) => write_string(out, value),
+        Json::Array(items) if items.is_empty() => out.push_str("[]"),
+        Json::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                newline(out, depth + 1);
+                write_json(out, item, depth + 1);
+            }
+            newline(out, depth);
+            out.push(']');
+        }
+        Json::Object(fields) => {
+            out.push('{');
+            for (i, (key, value)) in fields.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                newline(out, depth + 1);
+                write_string(out, key);
+                out.push_str(": ");
+                write_json(out, value, depth + 1);
+            }
+            newline(out, depth);
+            out.push('}');
+        }
+    }
+}
+
+fn newline(out: &mut String, depth: usize) {
+    out.push('\n');
+    out.push_str(&"  ".repeat(depth));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Engine;
+    use crate::parser::Parser;
+    use crate::scanner::Scanner;
+
+    const PROGRAM: &str = "var x = 1 + 2 * 3;
+fun f(a, b = 2, ...r) { return -a; }
+if (x > 1 and !false) print \"big\"; else { x = nil; }
+while (true) { break; }
+for (var i = 0; i < 2; i = i + 1) continue;
+try { throw \"e\"; } catch (e) { print e.message; } finally { print 1; }
+import util;
+var l = |y| y[0];
+f(1, b: 3).p;
+";
+
+    fn parse(source: &str) -> Vec<Statement> {
+        let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+        Parser::new(tokens).parse().unwrap()
+    }
+
+    #[test]
+    fn sexpr_covers_every_statement() {
+        let expected = "(var x (+ 1 (* 2 3)))
+(fun f (a (= b 2) (... r))
+  (return (- a)))
+(if (and (> x 1) (! false))
+  (print \"big\")
+  (block
+    (expr (= x nil))))
+(while true
+  (block
+    (break)))
+(for (var i 0) (< i 2) (= i (+ i 1))
+  (continue))
+(try
+  (throw \"e\")
+  (catch e
+    (print (. e message)))
+  (finally
+    (print 1)))
+(import util)
+(var l (lambda (y)
+  (return (index y 0))))
+(expr (. (call f 1 (: b 3)) p))
+";
+        assert_eq!(sexpr(&parse(PROGRAM)), expected);
+    }
+
+    #[test]
+    fn json_mirrors_the_variants() {
+        let expected = r#"[
+  {
+    "type": "Print",
+    "expr": {
+      "type": "Binary",
+      "left": {
+        "type": "Literal",
+        "value": 1
+      },
+      "operator": "+",
+      "right": {
+        "type": "Literal",
+        "value": 2.5
+      },
+      "line": 1
+    },
+    "line": 1
+  }
+]
+"#;
+        assert_eq!(json(&parse("print 1 + 2.5;")), expected);
+    }
+
+    #[test]
+    fn json_is_well_formed() {
+        let mut engine = Engine::new();
+        let dump = json(&parse(&format!("{}var s = \"two\nlines\";", PROGRAM)));
+        engine.interpreter().set_global("dump", dump);
+        let value = engine.eval("var ast = json.parse(dump); len(ast);");
+        assert_eq!(value.unwrap().to_string(), "10");
+        let value = engine.eval("ast[9].expr.value;").unwrap();
+        assert_eq!(value.to_string(), "two\nlines");
+    }
+
+    #[test]
+    fn an_empty_program_has_an_empty_dump() {
+        assert_eq!(sexpr(&[]), "");
+        assert_eq!(json(&[]), "[]\n");
+    }
+}
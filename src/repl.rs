@@ -0,0 +1,385 @@
+use std::borrow::Cow;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::time::Instant;
+
+use ownpy::scanner::KEYWORDS;
+use ownpy::{printer, Engine, Error, Interpreter, Parser, Scanner, TokenType, Value};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{CompletionType, Config, Context, Editor, Helper};
+
+const PROMPT: &str = "> ";
+const CONTINUATION_PROMPT: &str = "... ";
+const HISTORY_FILE: &str = ".ownpy_history";
+const HELP: &str = "\
+Enter statements or expressions; the values of expressions are printed.
+  :tokens <code>  show the tokens scanned from <code>
+  :ast <code>     show the syntax tree parsed from <code> as S-expressions
+  :env            list the globals defined in this session
+  :load <file>    run a script in this session
+  :reset          forget everything defined in this session
+  :time <code>    run <code> and show how long it took
+  :help           show this message
+Ctrl-C cancels the current input and Ctrl-D exits.";
+
+type ReplEditor = Editor<Completions, DefaultHistory>;
+
+pub fn run(engine: &mut Engine) {
+    // List every candidate on an ambiguous tab rather than cycling through them.
+    let config = Config::builder()
+        .completion_type(CompletionType::List)
+        .build();
+    let mut editor: ReplEditor = match Editor::with_config(config) {
+        Ok(editor) => editor,
+        Err(error) => {
+            eprintln!("Failed to start the REPL: {}", error);
+            process::exit(crate::EX_IOERR)
+        }
+    };
+    editor.set_helper(Some(Completions::default()));
+    let history = history_path();
+    if let Some(path) = &history {
+        // A missing history file just means this is the first session.
+        let _ = editor.load_history(path);
+    }
+
+    let mut source = String::new();
+    loop {
+        if let Some(completions) = editor.helper_mut() {
+            completions.bindings = engine.interpreter().bindings();
+        }
+        let prompt = match source.is_empty() {
+            true => PROMPT,
+            false => CONTINUATION_PROMPT,
+        };
+        match editor.readline(prompt) {
+            Ok(line) if source.is_empty() && line.starts_with(':') => {
+                let _ = editor.add_history_entry(line.as_str());
+                if let Err(code) = run_command(engine, &line) {
+                    save_history(&mut editor, &history);
+                    process::exit(code);
+                }
+            }
+            Ok(line) => {
+                source.push_str(&line);
+                source.push('\n');
+                if is_incomplete(&source) {
+                    continue;
+                }
+                let entry = std::mem::take(&mut source);
+                let entry = entry.trim_end();
+                if entry.is_empty() {
+                    continue;
+                }
+                let _ = editor.add_history_entry(entry);
+                if let Err(code) = evaluate(engine, entry) {
+                    save_history(&mut editor, &history);
+                    process::exit(code);
+                }
+            }
+            // Ctrl-C abandons the current input, Ctrl-D ends the session.
+            Err(ReadlineError::Interrupted) => source.clear(),
+            Err(ReadlineError::Eof) => break,
+            Err(error) => {
+                eprintln!("Failed to read input: {}", error);
+                break;
+            }
+        }
+    }
+    save_history(&mut editor, &history);
+}
+
+// Runs an entry and prints the value of a trailing expression unless it is
+// nil. Errors were already reported by the engine; only `exit()` ends the
+// session, with its code as the error.
+fn evaluate(engine: &mut Engine, source: &str) -> Result<(), i32> {
+    match engine.eval(&with_semicolon(source)) {
+        Ok(Value::None) | Err(Error::Scan(_) | Error::Parse(_) | Error::Runtime(_)) => Ok(()),
+        Ok(value) => {
+            println!("{}", value);
+            Ok(())
+        }
+        Err(Error::Exit(code)) => Err(code),
+    }
+}
+
+// Lets a single expression or statement be entered without its final `;`.
+fn with_semicolon(source: &str) -> Cow<'_, str> {
+    let parses = |source: &str| {
+        Scanner::new(source.to_string())
+            .scan_tokens()
+            .is_ok_and(|tokens| Parser::new(tokens).parse().is_ok())
+    };
+    let terminated = format!("{};", source);
+    if !parses(source) && parses(&terminated) {
+        Cow::Owned(terminated)
+    } else {
+        Cow::Borrowed(source)
+    }
+}
+
+fn run_command(engine: &mut Engine, line: &str) -> Result<(), i32> {
+    let (command, argument) = match line[1..].split_once(char::is_whitespace) {
+        Some((command, argument)) => (command, argument.trim()),
+        None => (&line[1..], ""),
+    };
+    match (command, argument) {
+        ("help", _) => println!("{}", HELP),
+        ("env", _) => {
+            for (name, value) in engine.interpreter().globals() {
+                println!("{} = {}", name, value);
+            }
+        }
+        ("reset", _) => engine.interpreter().reset(),
+        ("tokens" | "ast" | "load" | "time", "") => {
+            eprintln!(
+                "Usage: :{} <{}>",
+                command,
+                if command == "load" { "file" } else { "code" }
+            )
+        }
+        ("tokens", code) => match Scanner::new(code.to_string()).scan_tokens() {
+            Ok(tokens) => {
+                for token in tokens {
+                    println!("{:>4}  {}", token.line, token);
+                }
+            }
+            Err(errors) => eprintln!("{}", Error::Scan(errors)),
+        },
+        ("ast", code) => {
+            let parsed = Scanner::new(with_semicolon(code).into_owned())
+                .scan_tokens()
+                .map_err(Error::Scan)
+                .and_then(|tokens| Parser::new(tokens).parse().map_err(Error::Parse));
+            match parsed {
+                Ok(statements) => print!("{}", printer::sexpr(&statements)),
+                Err(error) => eprintln!("{}", error),
+            }
+        }
+        ("load", path) => match fs::read_to_string(path) {
+            Ok(source) => {
+                if let Err(Error::Exit(code)) = engine.eval(&source) {
+                    return Err(code);
+                }
+            }
+            Err(error) => eprintln!("Cannot read '{}': {}.", path, error),
+        },
+        ("time", code) => {
+            let start = Instant::now();
+            let result = evaluate(engine, code);
+            println!("Elapsed: {:.3} ms", start.elapsed().as_secs_f64() * 1000.0);
+            result?;
+        }
+        _ => eprintln!(
+            "Unknown command ':{}'. Type :help for a list of commands.",
+            command
+        ),
+    }
+    Ok(())
+}
+
+fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(HISTORY_FILE))
+}
+
+fn save_history(editor: &mut ReplEditor, history: &Option<PathBuf>) {
+    if let Some(path) = history {
+        if let Err(error) = editor.save_history(path) {
+            eprintln!("Failed to save history: {}", error);
+        }
+    }
+}
+
+// Input needs more lines while brackets are open or a string is unterminated.
+fn is_incomplete(source: &str) -> bool {
+    let tokens = match Scanner::new(source.to_string()).scan_tokens() {
+        Ok(tokens) => tokens,
+        Err(errors) => {
+            return errors
+                .iter()
+                .any(|error| error.message == "Unterminated string.")
+        }
+    };
+    let mut depth = 0;
+    for token in tokens {
+        match token.token_type {
+            TokenType::LeftParen | TokenType::LeftBrace | TokenType::LeftBracket => depth += 1,
+            TokenType::RightParen | TokenType::RightBrace | TokenType::RightBracket => depth -= 1,
+            _ => {}
+        }
+    }
+    depth > 0
+}
+
+// Tab completion of keywords and bound names, or of properties after a `.`.
+// Bindings are refreshed from the interpreter before each prompt.
+#[derive(Default)]
+struct Completions {
+    bindings: Vec<(String, Value)>,
+}
+impl Completions {
+    // Resolves a dotted path such as `math` or `config.db` to its value.
+    fn resolve(&self, path: &str) -> Option<Value> {
+        let mut segments = path.split('.');
+        let first = segments.next()?;
+        let mut value = self
+            .bindings
+            .iter()
+            .find(|(name, _)| name == first)
+            .map(|(_, value)| value.clone())?;
+        for segment in segments {
+            value = match value {
+                Value::Map(entries) => entries.borrow().get(segment).cloned()?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+}
+impl Completer for Completions {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let before = &line[..pos];
+        let start = before
+            .rfind(|c: char| !is_identifier_char(c))
+            .map_or(0, |index| index + 1);
+        let word = &before[start..];
+        let names: Vec<String> = match before[..start].strip_suffix('.') {
+            Some(object) if object.ends_with('"') => {
+                Interpreter::property_names(&Value::String(String::new()))
+            }
+            Some(object) => {
+                let path_start = object
+                    .rfind(|c: char| !is_identifier_char(c) && c != '.')
+                    .map_or(0, |index| index + 1);
+                match self.resolve(&object[path_start..]) {
+                    Some(value) => Interpreter::property_names(&value),
+                    None => Vec::new(),
+                }
+            }
+            None => KEYWORDS
+                .iter()
+                .map(|(keyword, _)| keyword.to_string())
+                .chain(self.bindings.iter().map(|(name, _)| name.clone()))
+                .collect(),
+        };
+        let mut candidates: Vec<String> = names
+            .into_iter()
+            .filter(|name| name.starts_with(word))
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        Ok((start, candidates))
+    }
+}
+impl Hinter for Completions {
+    type Hint = String;
+}
+impl Highlighter for Completions {}
+impl Validator for Completions {}
+impl Helper for Completions {}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_brackets_need_more_input() {
+        assert!(is_incomplete("fun f() {\n"));
+        assert!(is_incomplete("print max(1,\n"));
+        assert!(is_incomplete("if (true) { while (x) {\n}\n"));
+        assert!(is_incomplete("var s = \"one\n"));
+    }
+
+    #[test]
+    fn balanced_input_is_complete() {
+        assert!(!is_incomplete("fun f() {\n  return 1;\n}\n"));
+        assert!(!is_incomplete("print \"{\";\n"));
+        assert!(!is_incomplete("1 + 2\n"));
+        // Too many closers can never be completed, so they are left to the parser.
+        assert!(!is_incomplete("}\n"));
+        assert!(!is_incomplete("var x = @;\n"));
+    }
+
+    #[test]
+    fn a_missing_final_semicolon_is_added() {
+        assert_eq!(with_semicolon("1 + 2"), "1 + 2;");
+        assert_eq!(with_semicolon("var x = 1"), "var x = 1;");
+    }
+
+    #[test]
+    fn other_input_is_left_alone() {
+        assert!(matches!(with_semicolon("1 + 2;"), Cow::Borrowed(_)));
+        assert!(matches!(with_semicolon("fun f() {}"), Cow::Borrowed(_)));
+        // A semicolon wouldn't fix it, so the parser reports the real error.
+        assert!(matches!(with_semicolon("1 +"), Cow::Borrowed(_)));
+    }
+
+    // Completes at the end of `line` with a few script bindings defined.
+    fn complete(line: &str) -> (usize, Vec<String>) {
+        let mut engine = Engine::new();
+        engine
+            .interpreter()
+            .set_global("doc", r#"{"db": {"host": "h", "port": 1}}"#);
+        engine
+            .eval("var counter = 1; fun count() {} var config = json.parse(doc);")
+            .unwrap();
+        let completions = Completions {
+            bindings: engine.interpreter().bindings(),
+        };
+        let history = DefaultHistory::new();
+        completions
+            .complete(line, line.len(), &Context::new(&history))
+            .unwrap()
+    }
+
+    #[test]
+    fn keywords_and_bindings_complete() {
+        assert_eq!(complete("fo"), (0, vec!["for".to_string()]));
+        assert_eq!(
+            complete("print cou"),
+            (6, vec!["count".to_string(), "counter".to_string()])
+        );
+        assert_eq!(complete("l"), (0, vec!["len".to_string()]));
+    }
+
+    #[test]
+    fn properties_complete_after_a_dot() {
+        assert_eq!(complete("config.d"), (7, vec!["db".to_string()]));
+        assert_eq!(
+            complete("print config.db."),
+            (16, vec!["host".to_string(), "port".to_string()])
+        );
+        assert!(complete("math.sq").1.contains(&"sqrt".to_string()));
+        assert_eq!(
+            complete("\"abc\".rep"),
+            (6, vec!["repeat".to_string(), "replace".to_string()])
+        );
+    }
+
+    #[test]
+    fn unknown_names_have_no_completions() {
+        assert_eq!(complete("zzz"), (0, vec![]));
+        assert_eq!(complete("missing."), (8, vec![]));
+        assert_eq!(complete("counter.x"), (8, vec![]));
+    }
+}
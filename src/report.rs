@@ -0,0 +1,13 @@
+use crate::error::Error;
+
+pub trait Reporter {
+    fn report(&mut self, error: &Error);
+}
+
+pub struct StderrReporter;
+
+impl Reporter for StderrReporter {
+    fn report(&mut self, error: &Error) {
+        eprintln!("{}", error);
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+
+use crate::common::{Expr, Param, Statement, Token};
+use crate::interpreter::module_binding;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BindingKind {
+    Variable,
+    Function,
+    Parameter,
+    CatchVariable,
+    Import,
+}
+
+#[derive(Clone, Debug)]
+pub struct Binding {
+    pub name: String,
+    // The token that declares the binding. For `import "lib/utils"` that is
+    // the path, even though the binding is named `utils`.
+    pub token: Token,
+    pub kind: BindingKind,
+    // Declared at the top level of the script, where importers can see it.
+    pub global: bool,
+    pub reads: Vec<Token>,
+    pub writes: Vec<Token>,
+    // The binding of the same name in an enclosing scope that this one hides.
+    pub shadows: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+pub struct Resolution {
+    pub bindings: Vec<Binding>,
+    // Names no declaration in the script accounts for: builtins, or mistakes.
+    pub unresolved: Vec<Token>,
+}
+
+// Works out which declaration each variable reference refers to, following
+// the interpreter's scoping: blocks, calls, `for` loops and each part of a
+// `try` get their own scope, and a function's parameters share one with its
+// body. Globals are looked up when code runs, so functions may refer to
+// globals declared after them.
+pub fn resolve(statements: &[Statement]) -> Resolution {
+    let mut resolver = Resolver {
+        resolution: Resolution::default(),
+        scopes: vec![HashMap::new()],
+        function_depth: 0,
+        deferred: Vec::new(),
+    };
+    resolver.statements(statements);
+    for (token, is_write) in std::mem::take(&mut resolver.deferred) {
+        match resolver.scopes[0].get(&token.lexeme) {
+            Some(&index) => resolver.record(index, token, is_write),
+            None => resolver.resolution.unresolved.push(token),
+        }
+    }
+    resolver.resolution
+}
+
+struct Resolver {
+    resolution: Resolution,
+    // Innermost last; each maps a name to its index in `resolution.bindings`.
+    scopes: Vec<HashMap<String, usize>>,
+    function_depth: usize,
+    // References inside functions to names no scope declares yet.
+    deferred: Vec<(Token, bool)>,
+}
+
+impl Resolver {
+    fn scoped(&mut self, resolve: impl FnOnce(&mut Self)) {
+        self.scopes.push(HashMap::new());
+        resolve(self);
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: String, token: &Token, kind: BindingKind) {
+        let shadows = self.scopes[..self.scopes.len() - 1]
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name).copied());
+        let index = self.resolution.bindings.len();
+        self.resolution.bindings.push(Binding {
+            name: name.clone(),
+            token: token.clone(),
+            kind,
+            global: self.scopes.len() == 1,
+            reads: Vec::new(),
+            writes: Vec::new(),
+            shadows,
+        });
+        self.scopes.last_mut().unwrap().insert(name, index);
+    }
+
+    fn reference(&mut self, token: &Token, is_write: bool) {
+        let found = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&token.lexeme).copied());
+        match found {
+            Some(index) => self.record(index, token.clone(), is_write),
+            None if self.function_depth > 0 => self.deferred.push((token.clone(), is_write)),
+            None => self.resolution.unresolved.push(token.clone()),
+        }
+    }
+
+    fn record(&mut self, index: usize, token: Token, is_write: bool) {
+        let binding = &mut self.resolution.bindings[index];
+        match is_write {
+            true => binding.writes.push(token),
+            false => binding.reads.push(token),
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn function(&mut self, params: &[Param], body: &[Statement]) {
+        self.function_depth += 1;
+        self.scoped(|resolver| {
+            for param in params {
+                if let Some(default) = &param.default {
+                    resolver.expr(default);
+                }
+                let name = param.name.lexeme.clone();
+                resolver.declare(name, &param.name, BindingKind::Parameter);
+            }
+            resolver.statements(body);
+        });
+        self.function_depth -= 1;
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Expr { expr } | Statement::Print { expr, .. } => self.expr(expr),
+            // The initializer runs before the name is defined.
+            Statement::Var { name, expr } => {
+                self.expr(expr);
+                self.declare(name.lexeme.clone(), name, BindingKind::Variable);
+            }
+            Statement::Block { statements, .. } => {
+                self.scoped(|resolver| resolver.statements(statements))
+            }
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.expr(condition);
+                self.statement(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch);
+                }
+            }
+            Statement::While {
+                condition, body, ..
+            } => {
+                self.expr(condition);
+                self.statement(body);
+            }
+            Statement::For {
+                initializer,
+                condition,
+                increment,
+                body,
+                ..
+            } => self.scoped(|resolver| {
+                if let Some(initializer) = initializer {
+                    resolver.statement(initializer);
+                }
+                if let Some(condition) = condition {
+                    resolver.expr(condition);
+                }
+                if let Some(increment) = increment {
+                    resolver.expr(increment);
+                }
+                resolver.statement(body);
+            }),
+            Statement::Break { .. } | Statement::Continue { .. } => {}
+            // Declared before the body so the function can call itself.
+            Statement::Function { name, params, body } => {
+                self.declare(name.lexeme.clone(), name, BindingKind::Function);
+                self.function(params, body);
+            }
+            Statement::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            Statement::Throw { value, .. } => self.expr(value),
+            Statement::Try {
+                body,
+                catch_clause,
+                finally_body,
+            } => {
+                self.scoped(|resolver| resolver.statements(body));
+                if let Some((name, body)) = catch_clause {
+                    self.scoped(|resolver| {
+                        resolver.declare(name.lexeme.clone(), name, BindingKind::CatchVariable);
+                        resolver.statements(body);
+                    });
+                }
+                if let Some(body) = finally_body {
+                    self.scoped(|resolver| resolver.statements(body));
+                }
+            }
+            Statement::Import { module, alias, .. } => match alias {
+                Some(alias) => self.declare(alias.lexeme.clone(), alias, BindingKind::Import),
+                None => self.declare(module_binding(module), module, BindingKind::Import),
+            },
+            Statement::FromImport { names, .. } => {
+                for name in names {
+                    self.declare(name.lexeme.clone(), name, BindingKind::Import);
+                }
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Grouping { expression } => self.expr(expression),
+            Expr::Literal { .. } => {}
+            Expr::Unary { right, .. } => self.expr(right),
+            Expr::Var { name } => self.reference(name, false),
+            Expr::Assign { name, value } => {
+                self.expr(value);
+                self.reference(name, true);
+            }
+            Expr::Call {
+                callee,
+                arguments,
+                keyword_arguments,
+                ..
+            } => {
+                self.expr(callee);
+                for argument in arguments {
+                    self.expr(argument);
+                }
+                for (_, value) in keyword_arguments {
+                    self.expr(value);
+                }
+            }
+            Expr::Lambda { params, body } => self.function(params, body),
+            Expr::Get { object, .. } => self.expr(object),
+            Expr::Index { object, index, .. } => {
+                self.expr(object);
+                self.expr(index);
+            }
+            Expr::Slice {
+                object, start, end, ..
+            } => {
+                self.expr(object);
+                if let Some(start) = start {
+                    self.expr(start);
+                }
+                if let Some(end) = end {
+                    self.expr(end);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::scanner::Scanner;
+
+    fn resolve_source(source: &str) -> Resolution {
+        let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+        resolve(&Parser::new(tokens).parse().unwrap())
+    }
+
+    fn binding<'a>(resolution: &'a Resolution, name: &str, line: usize) -> &'a Binding {
+        let mut bindings = resolution.bindings.iter();
+        bindings
+            .find(|binding| binding.name == name && binding.token.line == line)
+            .unwrap()
+    }
+
+    fn lines(tokens: &[Token]) -> Vec<usize> {
+        tokens.iter().map(|token| token.line).collect()
+    }
+
+    #[test]
+    fn references_go_to_the_innermost_declaration() {
+        let resolution = resolve_source("var a = 1;\n{\n  var a = 2;\n  print a;\n}\nprint a;\n");
+        let outer = binding(&resolution, "a", 1);
+        let inner = binding(&resolution, "a", 3);
+        assert!(outer.global && !inner.global);
+        assert_eq!(lines(&outer.reads), [6]);
+        assert_eq!(lines(&inner.reads), [4]);
+        assert_eq!(inner.shadows, Some(0));
+    }
+
+    #[test]
+    fn reads_and_writes_are_kept_apart() {
+        let resolution = resolve_source("var n = 0;\nn = n + 1;\nn = 5;\n");
+        let n = binding(&resolution, "n", 1);
+        assert_eq!(lines(&n.reads), [2]);
+        assert_eq!(lines(&n.writes), [2, 3]);
+    }
+
+    #[test]
+    fn functions_see_globals_declared_after_them() {
+        let resolution = resolve_source("fun f() {\n  return later;\n}\nvar later = 1;\n");
+        assert_eq!(lines(&binding(&resolution, "later", 4).reads), [2]);
+        assert!(resolution.unresolved.is_empty());
+    }
+
+    #[test]
+    fn top_level_code_cannot_see_later_globals() {
+        let resolution = resolve_source("print early;\nvar early = 1;\n");
+        assert!(binding(&resolution, "early", 2).reads.is_empty());
+        assert_eq!(lines(&resolution.unresolved), [1]);
+    }
+
+    #[test]
+    fn bindings_have_kinds() {
+        let source =
+            "import \"lib/utils\";\nfun f(p) {\n  try {} catch (e) {}\n}\nvar v = |q| q;\n";
+        let resolution = resolve_source(source);
+        let kinds: Vec<(&str, BindingKind)> = resolution
+            .bindings
+            .iter()
+            .map(|binding| (binding.name.as_str(), binding.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("utils", BindingKind::Import),
+                ("f", BindingKind::Function),
+                ("p", BindingKind::Parameter),
+                ("e", BindingKind::CatchVariable),
+                ("q", BindingKind::Parameter),
+                ("v", BindingKind::Variable),
+            ]
+        );
+    }
+
+    #[test]
+    fn builtins_are_unresolved() {
+        let resolution = resolve_source("print clock();\nprint missing;\n");
+        let names: Vec<&str> = resolution
+            .unresolved
+            .iter()
+            .map(|token| token.lexeme.as_str())
+            .collect();
+        assert_eq!(names, ["clock", "missing"]);
+    }
+}
@@ -1,45 +1,97 @@
-use crate::common::{LiteralValue, Token, TokenType};
+use crate::common::{LiteralValue, Token, TokenType, Trivia, TriviaKind};
+use crate::error::ScanError;
 use crate::utils::{is_alpha, is_alpha_numeric, is_digit};
 
+pub const KEYWORDS: &[(&str, TokenType)] = &[
+    ("and", TokenType::And),
+    ("class", TokenType::Class),
+    ("else", TokenType::Else),
+    ("false", TokenType::False),
+    ("fun", TokenType::Fun),
+    ("for", TokenType::For),
+    ("if", TokenType::If),
+    ("nil", TokenType::Nil),
+    ("or", TokenType::Or),
+    ("print", TokenType::Print),
+    ("return", TokenType::Return),
+    ("super", TokenType::Super),
+    ("this", TokenType::This),
+    ("true", TokenType::True),
+    ("var", TokenType::Var),
+    ("while", TokenType::While),
+    ("break", TokenType::Break),
+    ("continue", TokenType::Continue),
+    ("throw", TokenType::Throw),
+    ("try", TokenType::Try),
+    ("catch", TokenType::Catch),
+    ("finally", TokenType::Finally),
+    ("import", TokenType::Import),
+    ("from", TokenType::From),
+    ("as", TokenType::As),
+];
+
 pub struct Scanner {
     source_code: String,
     tokens: Vec<Token>,
-    start: u8,
-    curr: u8,
-    line: u8,
+    errors: Vec<ScanError>,
+    start: usize,
+    curr: usize,
+    line: usize,
+    keep_trivia: bool,
+    // Trivia seen since the last token, in trivia-keeping mode.
+    trivia: Vec<Trivia>,
 }
 impl Scanner {
     pub fn new(source_code: String) -> Self {
         Scanner {
             source_code,
             tokens: Vec::new(),
+            errors: Vec::new(),
             start: 0,
             curr: 0,
             line: 1,
+            keep_trivia: false,
+            trivia: Vec::new(),
+        }
+    }
+    // Attaches whitespace and comments to the tokens around them, so the
+    // tokens' trivia and lexemes together spell out the whole source.
+    pub fn with_trivia(source_code: String) -> Self {
+        Scanner {
+            keep_trivia: true,
+            ..Scanner::new(source_code)
         }
     }
     fn is_at_end(&self) -> bool {
-        self.curr >= self.source_code.len() as u8
+        self.curr >= self.source_code.len()
     }
-    pub fn scan_tokens(&mut self) -> Vec<Token> {
-        let tokens: Vec<Token>;
+    pub fn scan_tokens(&mut self) -> Result<Vec<Token>, Vec<ScanError>> {
         while !self.is_at_end() {
             self.start = self.curr;
             self.scan_single_token();
         }
-        self.tokens.extend([Token::new(
-            TokenType::EOF,
-            "".to_string(),
-            LiteralValue::None,
-            self.line,
-        )]);
-        tokens = self.tokens.to_vec();
-        println!("{:?}", tokens);
-        tokens
+        self.start = self.curr;
+        self.add_token(TokenType::Eof, LiteralValue::None);
+        if self.keep_trivia {
+            self.split_trailing_trivia();
+        }
+        let tokens = self.tokens.to_vec();
+        if self.errors.is_empty() {
+            Ok(tokens)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+    fn error(&mut self, message: String) {
+        self.errors.push(ScanError {
+            line: self.line,
+            message,
+        });
     }
 
+    // Offsets are byte offsets into the source, so they always fall on char boundaries.
     fn get_char(&self, idx: usize) -> Option<char> {
-        self.source_code.chars().nth(idx)
+        self.source_code.get(idx..)?.chars().next()
     }
 
     fn get_substring(&self, start: usize, end: usize) -> Option<&str> {
@@ -50,31 +102,31 @@ impl Scanner {
         if self.is_at_end() {
             return false;
         }
-        if self.get_char(self.curr as usize).unwrap() != expected {
+        if self.get_char(self.curr).unwrap() != expected {
             return false;
         }
-        self.curr += 1;
+        self.curr += expected.len_utf8();
         true
     }
 
     fn peek(&mut self) -> char {
         match self.is_at_end() {
             true => '\0',
-            false => self.get_char(self.curr as usize).unwrap(),
+            false => self.get_char(self.curr).unwrap(),
         }
     }
 
     fn peek_next(&mut self) -> char {
-        if self.curr + 1 >= self.source_code.len() as u8 {
-            return '\0';
+        match self.get_char(self.curr) {
+            Some(c) => self.get_char(self.curr + c.len_utf8()).unwrap_or('\0'),
+            None => '\0',
         }
-        self.get_char((self.curr + 1) as usize).unwrap()
     }
 
     fn next(&mut self) -> char {
-        match self.get_char(self.curr as usize) {
+        match self.get_char(self.curr) {
             Some(c) => {
-                self.curr += 1;
+                self.curr += c.len_utf8();
                 c
             }
             None => panic!("Empty char"),
@@ -95,11 +147,22 @@ impl Scanner {
             '}' => {
                 self.add_token(TokenType::RightBrace, LiteralValue::None);
             }
+            '[' => self.add_token(TokenType::LeftBracket, LiteralValue::None),
+            ']' => self.add_token(TokenType::RightBracket, LiteralValue::None),
             ',' => {
                 self.add_token(TokenType::Comma, LiteralValue::None);
             }
             '.' => {
-                self.add_token(TokenType::Dot, LiteralValue::None);
+                if self.peek() == '.' && self.peek_next() == '.' {
+                    self.next();
+                    self.next();
+                    self.add_token(TokenType::Ellipsis, LiteralValue::None);
+                } else {
+                    self.add_token(TokenType::Dot, LiteralValue::None);
+                }
+            }
+            ':' => {
+                self.add_token(TokenType::Colon, LiteralValue::None);
             }
             '-' => {
                 self.add_token(TokenType::Minus, LiteralValue::None);
@@ -142,35 +205,49 @@ impl Scanner {
             '/' => {
                 match self.match_expr('/') {
                     true => {
-                        while self.peek() != '\n' && !self.is_at_end() {
+                        while !matches!((self.peek(), self.peek_next()), ('\n', _) | ('\r', '\n'))
+                            && !self.is_at_end()
+                        {
                             self.next();
                         }
+                        self.add_trivia(TriviaKind::LineComment);
                     }
                     false => match self.match_expr('*') {
                         true => {
                             while !self.is_at_end() {
-                                self.next();
                                 if self.peek() == '*' && self.peek_next() == '/' {
                                     self.next();
                                     self.next();
                                     break;
                                 }
+                                if self.next() == '\n' {
+                                    self.line += 1;
+                                }
                             }
+                            self.add_trivia(TriviaKind::BlockComment);
                         }
                         false => self.add_token(TokenType::Slash, LiteralValue::None),
                     },
                 };
             }
             '"' => self.scan_string(),
-            '\n' => self.line += 1,
-            ' ' | '\r' | '\t' => {}
+            '\n' => {
+                self.line += 1;
+                self.add_trivia(TriviaKind::Newline);
+            }
+            '\r' if self.peek() == '\n' => {
+                self.next();
+                self.line += 1;
+                self.add_trivia(TriviaKind::Newline);
+            }
+            ' ' | '\r' | '\t' => self.add_trivia(TriviaKind::Whitespace),
             _ => {
                 if is_digit(c) {
                     self.scan_number()
                 } else if is_alpha(c) {
                     self.scan_identifer()
                 } else {
-                    panic!("Unexpected character.")
+                    self.error(format!("Unexpected character '{c}'."))
                 }
             }
         }
@@ -180,28 +257,11 @@ impl Scanner {
         while is_alpha_numeric(self.peek()) && !self.is_at_end() {
             self.next();
         }
-        let text = self
-            .get_substring(self.start as usize, self.curr as usize)
-            .unwrap();
-        let token_type: TokenType = match text {
-            "and" => TokenType::And,
-            "class" => TokenType::Class,
-            "else" => TokenType::Else,
-            "false" => TokenType::False,
-            "fun" => TokenType::Fun,
-            "for" => TokenType::For,
-            "if" => TokenType::If,
-            "nil" => TokenType::Nil,
-            "or" => TokenType::Or,
-            "print" => TokenType::Print,
-            "return" => TokenType::Return,
-            "super" => TokenType::Super,
-            "this" => TokenType::This,
-            "true" => TokenType::True,
-            "var" => TokenType::Var,
-            "while" => TokenType::While,
-            _ => TokenType::Identifier,
-        };
+        let text = self.get_substring(self.start, self.curr).unwrap();
+        let token_type = KEYWORDS
+            .iter()
+            .find(|(keyword, _)| *keyword == text)
+            .map_or(TokenType::Identifier, |(_, token_type)| token_type.clone());
         self.add_token(token_type, LiteralValue::None)
     }
 
@@ -213,12 +273,13 @@ impl Scanner {
             self.next();
         }
         if self.is_at_end() {
-            panic!("Unterminated string.")
+            self.error("Unterminated string.".to_string());
+            return;
         }
         self.next();
 
         let text = self
-            .get_substring((self.start + 1) as usize, (self.curr - 1) as usize)
+            .get_substring(self.start + 1, self.curr - 1)
             .unwrap()
             .to_string();
         self.add_token(TokenType::String, LiteralValue::String(text));
@@ -228,7 +289,9 @@ impl Scanner {
         while is_digit(self.peek()) {
             self.next();
         }
+        let mut is_float = false;
         if self.peek() == '.' && is_digit(self.peek_next()) {
+            is_float = true;
             self.next();
         }
         while is_digit(self.peek()) {
@@ -236,25 +299,104 @@ impl Scanner {
         }
 
         let text = self
-            .get_substring(self.start as usize, self.curr as usize)
+            .get_substring(self.start, self.curr)
             .unwrap()
             .to_string();
 
-        self.add_token(
-            TokenType::Number,
-            LiteralValue::Float(text.parse::<f64>().unwrap()),
-        );
+        // Literals without a fractional part are integers unless they don't fit in an i64.
+        let literal_value = match text.parse::<i64>() {
+            Ok(value) if !is_float => LiteralValue::Integer(value),
+            _ => LiteralValue::Float(text.parse::<f64>().unwrap()),
+        };
+        self.add_token(TokenType::Number, literal_value);
     }
 
-    fn add_token(&mut self, token_type: TokenType, literal_value: LiteralValue) {
+    fn add_trivia(&mut self, kind: TriviaKind) {
+        if !self.keep_trivia {
+            return;
+        }
         let text = self
-            .get_substring(self.start as usize, self.curr as usize)
-            .unwrap();
-        self.tokens.extend([Token::new(
-            token_type,
-            text.to_string(),
-            literal_value,
-            self.line,
-        )]);
+            .get_substring(self.start, self.curr)
+            .unwrap()
+            .to_string();
+        match self.trivia.last_mut() {
+            Some(last) if kind == TriviaKind::Whitespace && last.kind == kind => {
+                last.text.push_str(&text)
+            }
+            _ => self.trivia.push(Trivia { kind, text }),
+        }
+    }
+
+    // Everything up to the first newline after a token moves from the next
+    // token's leading trivia to its own trailing trivia.
+    fn split_trailing_trivia(&mut self) {
+        for i in 1..self.tokens.len() {
+            let leading = std::mem::take(&mut self.tokens[i].leading_trivia);
+            let end = leading
+                .iter()
+                .position(|trivia| trivia.kind == TriviaKind::Newline)
+                .unwrap_or(leading.len());
+            let (trailing, leading) = leading.split_at(end);
+            self.tokens[i - 1].trailing_trivia = trailing.into();
+            self.tokens[i].leading_trivia = leading.into();
+        }
+    }
+
+    fn add_token(&mut self, token_type: TokenType, literal_value: LiteralValue) {
+        let text = self.get_substring(self.start, self.curr).unwrap();
+        let mut token = Token::new(token_type, text.to_string(), literal_value, self.line);
+        token.offset = self.start;
+        token.leading_trivia = std::mem::take(&mut self.trivia).into_boxed_slice();
+        self.tokens.push(token);
+    }
+}
+
+// A comment from a trivia-keeping scan, placed in the source.
+#[derive(Clone, Copy, Debug)]
+pub struct Comment<'a> {
+    pub text: &'a str,
+    // The line the comment starts on, and its byte offset.
+    pub line: usize,
+    pub offset: usize,
+    // Index of the token the comment comes before.
+    pub token: usize,
+}
+
+impl Comment<'_> {
+    pub fn end_line(&self) -> usize {
+        self.line + self.text.matches('\n').count()
+    }
+}
+
+// Collects the comments in the trivia of tokens from `Scanner::with_trivia`,
+// in source order. Tokens carry the line they end on, so lines are counted on
+// from the end of the token before each comment.
+pub fn comments(tokens: &[Token]) -> Vec<Comment<'_>> {
+    let mut comments = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let (mut line, mut offset, trailing) = match i.checked_sub(1).map(|i| &tokens[i]) {
+            Some(previous) => (
+                previous.line,
+                previous.offset + previous.lexeme.len(),
+                &previous.trailing_trivia[..],
+            ),
+            None => (1, 0, &[][..]),
+        };
+        for trivia in trailing.iter().chain(token.leading_trivia.iter()) {
+            if matches!(
+                trivia.kind,
+                TriviaKind::LineComment | TriviaKind::BlockComment
+            ) {
+                comments.push(Comment {
+                    text: &trivia.text,
+                    line,
+                    offset,
+                    token: i,
+                });
+            }
+            line += trivia.text.matches('\n').count();
+            offset += trivia.text.len();
+        }
     }
+    comments
 }
@@ -0,0 +1,193 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::io::Write;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{error, float_to_int, too_large, unexpected_type, MAX_LIST_SIZE};
+use crate::common::LiteralValue;
+use crate::convert::{arg, FromValue};
+use crate::error::RuntimeError;
+use crate::function::Arity;
+use crate::interpreter::Interpreter;
+
+pub(super) fn register(interpreter: &mut Interpreter) {
+    interpreter.register_fn("clock", 0, clock);
+    interpreter.register_fn("len", 1, len);
+    interpreter.register_fn("type", 1, type_of);
+    interpreter.register_fn("typeof", 1, type_of);
+    interpreter.register_fn("str", 1, |args| {
+        Ok(LiteralValue::String(args[0].to_string()))
+    });
+    interpreter.register_fn("int", 1, int);
+    interpreter.register_fn("float", 1, float);
+    interpreter.register_fn("bool", 1, |args| {
+        Ok(LiteralValue::Bool(args[0].is_truthy()))
+    });
+    let output = interpreter.output();
+    interpreter.register_fn("input", Arity::Range(0, 1), move |args| {
+        input(&output, args)
+    });
+    interpreter.register_fn("assert", Arity::Range(1, 2), assert);
+    interpreter.register_fn("exit", Arity::Range(0, 1), exit);
+    interpreter.register_fn("min", Arity::AtLeast(1), |args| {
+        extremum(args, Ordering::Less)
+    });
+    interpreter.register_fn("max", Arity::AtLeast(1), |args| {
+        extremum(args, Ordering::Greater)
+    });
+    interpreter.register_fn("abs", 1, abs);
+    interpreter.register_fn("range", Arity::Range(1, 3), range);
+}
+
+fn clock(_args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|err| error(err.to_string()))?;
+    Ok(LiteralValue::Float(elapsed.as_secs_f64()))
+}
+
+fn len(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let length = match &args[0] {
+        LiteralValue::String(value) => value.chars().count(),
+        LiteralValue::List(items) => items.borrow().len(),
+        LiteralValue::Map(entries) => entries.borrow().len(),
+        _ => return Err(unexpected_type("string, list or map", args, 0)),
+    };
+    Ok(LiteralValue::Integer(length as i64))
+}
+
+fn type_of(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    Ok(LiteralValue::String(args[0].type_name().to_string()))
+}
+
+fn int(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    match &args[0] {
+        LiteralValue::Integer(value) => Ok(LiteralValue::Integer(*value)),
+        LiteralValue::Bool(value) => Ok(LiteralValue::Integer(*value as i64)),
+        LiteralValue::Float(value) => float_to_int(*value).map(LiteralValue::Integer),
+        LiteralValue::String(value) => value
+            .trim()
+            .parse::<i64>()
+            .map(LiteralValue::Integer)
+            .map_err(|_| error(format!("Invalid literal for int: '{}'.", value))),
+        _ => Err(unexpected_type("number, string or bool", args, 0)),
+    }
+}
+
+fn float(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    match &args[0] {
+        LiteralValue::Float(value) => Ok(LiteralValue::Float(*value)),
+        LiteralValue::Integer(value) => Ok(LiteralValue::Float(*value as f64)),
+        LiteralValue::Bool(value) => Ok(LiteralValue::Float(*value as i64 as f64)),
+        LiteralValue::String(value) => value
+            .trim()
+            .parse::<f64>()
+            .map(LiteralValue::Float)
+            .map_err(|_| error(format!("Invalid literal for float: '{}'.", value))),
+        _ => Err(unexpected_type("number, string or bool", args, 0)),
+    }
+}
+
+fn input(
+    output: &Rc<RefCell<Box<dyn Write>>>,
+    args: &[LiteralValue],
+) -> Result<LiteralValue, RuntimeError> {
+    if let Some(prompt) = args.first() {
+        let mut output = output.borrow_mut();
+        write!(output, "{}", prompt)
+            .and_then(|_| output.flush())
+            .map_err(|err| error(format!("Failed to write output: {}", err)))?;
+    }
+    super::io::read_line(&[])
+}
+
+fn assert(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    if args[0].is_truthy() {
+        return Ok(LiteralValue::None);
+    }
+    match args.get(1) {
+        Some(message) => Err(error(format!("Assertion failed: {}", message))),
+        None => Err(error("Assertion failed.".to_string())),
+    }
+}
+
+fn exit(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let code = if args.is_empty() {
+        0
+    } else {
+        arg::<i64>(args, 0)?
+    };
+    let code =
+        i32::try_from(code).map_err(|_| error(format!("Exit code {} out of range.", code)))?;
+    Err(RuntimeError::exit(code))
+}
+
+fn compare(left: &LiteralValue, right: &LiteralValue) -> Result<Ordering, RuntimeError> {
+    match (left, right) {
+        (LiteralValue::Integer(left), LiteralValue::Integer(right)) => Ok(left.cmp(right)),
+        (LiteralValue::String(left), LiteralValue::String(right)) => Ok(left.cmp(right)),
+        _ => match (f64::from_value(left), f64::from_value(right)) {
+            (Ok(left), Ok(right)) => Ok(left.partial_cmp(&right).unwrap_or(Ordering::Equal)),
+            _ => Err(error(format!(
+                "Cannot compare {} and {}.",
+                left.type_name(),
+                right.type_name()
+            ))),
+        },
+    }
+}
+
+// `min` and `max` take either several arguments or a single list.
+fn extremum(args: &[LiteralValue], wanted: Ordering) -> Result<LiteralValue, RuntimeError> {
+    let items = match args {
+        [LiteralValue::List(items)] => items.borrow().clone(),
+        _ => args.to_vec(),
+    };
+    let mut items = items.into_iter();
+    let mut best = items
+        .next()
+        .ok_or_else(|| error("Expected a non-empty list.".to_string()))?;
+    for item in items {
+        if compare(&item, &best)? == wanted {
+            best = item;
+        }
+    }
+    Ok(best)
+}
+
+fn abs(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    match &args[0] {
+        LiteralValue::Integer(value) => value
+            .checked_abs()
+            .map(LiteralValue::Integer)
+            .ok_or_else(|| error("Integer overflow.".to_string())),
+        LiteralValue::Float(value) => Ok(LiteralValue::Float(value.abs())),
+        _ => Err(unexpected_type("number", args, 0)),
+    }
+}
+
+// range(stop), range(start, stop) or range(start, stop, step).
+fn range(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let (start, stop) = match args.len() {
+        1 => (0, arg::<i64>(args, 0)?),
+        _ => (arg::<i64>(args, 0)?, arg::<i64>(args, 1)?),
+    };
+    let step = match args.get(2) {
+        Some(_) => arg::<i64>(args, 2)?,
+        None => 1,
+    };
+    if step == 0 {
+        return Err(error("Step must not be zero.".to_string()));
+    }
+    // Widened so neither the span nor the rounding up can overflow.
+    let span = (stop as i128 - start as i128) * step.signum() as i128;
+    let count = (span.max(0) + step.unsigned_abs() as i128 - 1) / step.unsigned_abs() as i128;
+    if count > MAX_LIST_SIZE as i128 {
+        return Err(too_large());
+    }
+    let items = (0..count as i64)
+        .map(|i| LiteralValue::Integer((start as i128 + i as i128 * step as i128) as i64))
+        .collect();
+    Ok(LiteralValue::List(Rc::new(RefCell::new(items))))
+}
@@ -0,0 +1,94 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use super::{error, namespace, native};
+use crate::common::LiteralValue;
+use crate::convert::{arg, IntoValue};
+use crate::error::RuntimeError;
+use crate::function::Arity;
+
+pub(super) fn module() -> LiteralValue {
+    namespace(vec![
+        ("read_file", native("fs.read_file", 1, read_file)),
+        ("read_lines", native("fs.read_lines", 1, read_lines)),
+        ("write_file", native("fs.write_file", 2, write_file)),
+        ("append_file", native("fs.append_file", 2, append_file)),
+        ("exists", native("fs.exists", 1, exists)),
+        (
+            "list_dir",
+            native("fs.list_dir", Arity::Range(0, 1), list_dir),
+        ),
+        ("remove", native("fs.remove", 1, remove)),
+    ])
+}
+
+// I/O failures become runtime errors so scripts can catch them.
+fn failed(action: &str, path: &str, err: io::Error) -> RuntimeError {
+    error(format!("Cannot {} '{}': {}.", action, path, err))
+}
+
+fn read_file(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let path = arg::<String>(args, 0)?;
+    let content = fs::read_to_string(&path).map_err(|err| failed("read", &path, err))?;
+    Ok(LiteralValue::String(content))
+}
+
+fn read_lines(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let path = arg::<String>(args, 0)?;
+    let content = fs::read_to_string(&path).map_err(|err| failed("read", &path, err))?;
+    let lines: Vec<&str> = content.lines().collect();
+    Ok(lines.into_value())
+}
+
+fn write_file(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let path = arg::<String>(args, 0)?;
+    let content = arg::<String>(args, 1)?;
+    fs::write(&path, content).map_err(|err| failed("write", &path, err))?;
+    Ok(LiteralValue::None)
+}
+
+fn append_file(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let path = arg::<String>(args, 0)?;
+    let content = arg::<String>(args, 1)?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .map_err(|err| failed("write", &path, err))?;
+    Ok(LiteralValue::None)
+}
+
+fn exists(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let path = arg::<String>(args, 0)?;
+    Ok(LiteralValue::Bool(Path::new(&path).exists()))
+}
+
+// Lists entry names in `path`, the working directory by default, sorted.
+fn list_dir(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let path = match args.first() {
+        Some(_) => arg::<String>(args, 0)?,
+        None => ".".to_string(),
+    };
+    let mut names = fs::read_dir(&path)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().into_owned()))
+                .collect::<Result<Vec<String>, _>>()
+        })
+        .map_err(|err| failed("list", &path, err))?;
+    names.sort();
+    Ok(names.into_value())
+}
+
+// Removes a file or an empty directory.
+fn remove(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let path = arg::<String>(args, 0)?;
+    let result = match Path::new(&path).is_dir() {
+        true => fs::remove_dir(&path),
+        false => fs::remove_file(&path),
+    };
+    result.map_err(|err| failed("remove", &path, err))?;
+    Ok(LiteralValue::None)
+}
@@ -0,0 +1,56 @@
+use std::cell::RefCell;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+use super::{error, namespace, native};
+use crate::common::LiteralValue;
+use crate::convert::IntoValue;
+use crate::error::RuntimeError;
+
+pub(super) fn module(output: Rc<RefCell<Box<dyn Write>>>) -> LiteralValue {
+    namespace(vec![
+        ("read_line", native("io.read_line", 0, read_line)),
+        ("read_lines", native("io.read_lines", 0, read_lines)),
+        (
+            "write",
+            native("io.write", 1, move |args| {
+                let mut output = output.borrow_mut();
+                write!(output, "{}", args[0])
+                    .and_then(|_| output.flush())
+                    .map_err(|err| error(format!("Failed to write output: {}", err)))?;
+                Ok(LiteralValue::None)
+            }),
+        ),
+        ("eprint", native("io.eprint", 1, eprint)),
+    ])
+}
+
+// Reads one line from stdin without its line ending, or nil at end of input.
+pub(super) fn read_line(_args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let mut line = String::new();
+    let read = io::stdin()
+        .read_line(&mut line)
+        .map_err(|err| error(format!("Failed to read input: {}", err)))?;
+    if read == 0 {
+        return Ok(LiteralValue::None);
+    }
+    let trimmed = line.trim_end_matches(['\n', '\r']).len();
+    line.truncate(trimmed);
+    Ok(LiteralValue::String(line))
+}
+
+// Reads the rest of stdin as a list of lines.
+fn read_lines(_args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let lines = io::stdin()
+        .lock()
+        .lines()
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|err| error(format!("Failed to read input: {}", err)))?;
+    Ok(lines.into_value())
+}
+
+fn eprint(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    writeln!(io::stderr(), "{}", args[0])
+        .map_err(|err| error(format!("Failed to write to stderr: {}", err)))?;
+    Ok(LiteralValue::None)
+}
@@ -0,0 +1,436 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use super::{error, namespace, native};
+use crate::common::LiteralValue;
+use crate::convert::arg;
+use crate::error::RuntimeError;
+use crate::function::Arity;
+
+// Deeper documents are rejected rather than overflowing the native stack.
+const MAX_DEPTH: usize = 512;
+// Wider indents are an error, as they are pointless and could make huge strings.
+const MAX_INDENT: i64 = 10;
+
+pub(super) fn module() -> LiteralValue {
+    namespace(vec![
+        ("parse", native("json.parse", 1, parse)),
+        (
+            "stringify",
+            native("json.stringify", Arity::Range(1, 2), stringify),
+        ),
+    ])
+}
+
+fn parse(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    parse_json(&arg::<String>(args, 0)?)
+}
+
+pub(crate) fn parse_json(source: &str) -> Result<LiteralValue, RuntimeError> {
+    let mut parser = JsonParser {
+        source,
+        offset: 0,
+        line: 1,
+        column: 1,
+        depth: 0,
+    };
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    match parser.peek() {
+        Some(_) => Err(parser.error("unexpected trailing characters")),
+        None => Ok(value),
+    }
+}
+
+// stringify(value) is compact; stringify(value, indent) puts each item on its
+// own line, indented by `indent` spaces per level.
+fn stringify(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let indent = match args.get(1) {
+        Some(LiteralValue::None) | None => 0,
+        Some(_) => arg::<i64>(args, 1)?,
+    };
+    if !(0..=MAX_INDENT).contains(&indent) {
+        return Err(error(format!(
+            "Indent must be between 0 and {}.",
+            MAX_INDENT
+        )));
+    }
+    let indent = indent as usize;
+    let mut out = String::new();
+    write_value(&mut out, &args[0], indent, 0)?;
+    Ok(LiteralValue::String(out))
+}
+
+// Compact JSON, as `stringify(value)` writes it.
+pub(crate) fn to_json(value: &LiteralValue) -> Result<String, RuntimeError> {
+    let mut out = String::new();
+    write_value(&mut out, value, 0, 0)?;
+    Ok(out)
+}
+
+struct JsonParser<'a> {
+    source: &'a str,
+    offset: usize,
+    line: usize,
+    column: usize,
+    depth: usize,
+}
+impl JsonParser<'_> {
+    // Errors point at the character the parser is looking at.
+    fn error(&self, message: &str) -> RuntimeError {
+        error(format!(
+            "Invalid JSON at line {}, column {}: {}.",
+            self.line, self.column, message
+        ))
+    }
+    fn peek(&self) -> Option<char> {
+        self.source[self.offset..].chars().next()
+    }
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+    fn expect(&mut self, expected: char) -> Result<(), RuntimeError> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.next();
+                Ok(())
+            }
+            Some(c) => Err(self.error(&format!("expected '{}', found '{}'", expected, c))),
+            None => Err(self.error(&format!("expected '{}', found end of input", expected))),
+        }
+    }
+    fn skip_whitespace(&mut self) {
+        while let Some(' ' | '\t' | '\n' | '\r') = self.peek() {
+            self.next();
+        }
+    }
+    fn parse_value(&mut self) -> Result<LiteralValue, RuntimeError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.nested(Self::parse_object),
+            Some('[') => self.nested(Self::parse_array),
+            Some('"') => self.parse_string().map(LiteralValue::String),
+            Some('t') => self.parse_keyword("true", LiteralValue::Bool(true)),
+            Some('f') => self.parse_keyword("false", LiteralValue::Bool(false)),
+            Some('n') => self.parse_keyword("null", LiteralValue::None),
+            Some('-' | '0'..='9') => self.parse_number(),
+            Some(c) => Err(self.error(&format!("unexpected character '{}'", c))),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<LiteralValue, RuntimeError>,
+    ) -> Result<LiteralValue, RuntimeError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+    fn parse_keyword(
+        &mut self,
+        keyword: &str,
+        value: LiteralValue,
+    ) -> Result<LiteralValue, RuntimeError> {
+        for expected in keyword.chars() {
+            if self.peek() != Some(expected) {
+                return Err(self.error(&format!("invalid literal, expected '{}'", keyword)));
+            }
+            self.next();
+        }
+        Ok(value)
+    }
+    fn parse_object(&mut self) -> Result<LiteralValue, RuntimeError> {
+        self.expect('{')?;
+        let mut entries = BTreeMap::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.next();
+            return Ok(LiteralValue::Map(Rc::new(RefCell::new(entries))));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected string key"));
+            }
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            let value = self.parse_value()?;
+            entries.insert(key, value);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.next();
+                }
+                Some('}') => {
+                    self.next();
+                    return Ok(LiteralValue::Map(Rc::new(RefCell::new(entries))));
+                }
+                Some(c) => return Err(self.error(&format!("expected ',' or '}}', found '{}'", c))),
+                None => return Err(self.error("unterminated object")),
+            }
+        }
+    }
+    fn parse_array(&mut self) -> Result<LiteralValue, RuntimeError> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.next();
+            return Ok(LiteralValue::List(Rc::new(RefCell::new(items))));
+        }
+        loop {
+            items.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.next();
+                }
+                Some(']') => {
+                    self.next();
+                    return Ok(LiteralValue::List(Rc::new(RefCell::new(items))));
+                }
+                Some(c) => return Err(self.error(&format!("expected ',' or ']', found '{}'", c))),
+                None => return Err(self.error("unterminated array")),
+            }
+        }
+    }
+    fn parse_string(&mut self) -> Result<String, RuntimeError> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some('"') => {
+                    self.next();
+                    return Ok(value);
+                }
+                Some('\\') => {
+                    self.next();
+                    value.push(self.parse_escape()?);
+                }
+                Some(c) if (c as u32) < 0x20 => {
+                    return Err(self.error("control character in string"))
+                }
+                Some(c) => {
+                    self.next();
+                    value.push(c);
+                }
+            }
+        }
+    }
+    fn parse_escape(&mut self) -> Result<char, RuntimeError> {
+        let escaped = match self.peek() {
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('/') => '/',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('u') => {
+                self.next();
+                return self.parse_unicode_escape();
+            }
+            _ => return Err(self.error("invalid escape sequence")),
+        };
+        self.next();
+        Ok(escaped)
+    }
+    // Handles `\uXXXX`, including surrogate pairs written as two escapes.
+    fn parse_unicode_escape(&mut self) -> Result<char, RuntimeError> {
+        let high = self.parse_hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if self.peek() != Some('\\') {
+                return Err(self.error("unpaired surrogate in string"));
+            }
+            self.next();
+            if self.peek() != Some('u') {
+                return Err(self.error("unpaired surrogate in string"));
+            }
+            self.next();
+            let low = self.parse_hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(self.error("unpaired surrogate in string"));
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| self.error("unpaired surrogate in string"))
+    }
+    fn parse_hex4(&mut self) -> Result<u32, RuntimeError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .peek()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error("invalid unicode escape"))?;
+            self.next();
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+    // Numbers without a fraction or exponent become ints when they fit in an i64.
+    fn parse_number(&mut self) -> Result<LiteralValue, RuntimeError> {
+        let start = self.offset;
+        let mut is_float = false;
+        if self.peek() == Some('-') {
+            self.next();
+        }
+        match self.peek() {
+            Some('0') => {
+                self.next();
+            }
+            Some('1'..='9') => self.skip_digits(),
+            _ => return Err(self.error("expected digit")),
+        }
+        if self.peek() == Some('.') {
+            is_float = true;
+            self.next();
+            if !matches!(self.peek(), Some('0'..='9')) {
+                return Err(self.error("expected digit after decimal point"));
+            }
+            self.skip_digits();
+        }
+        if let Some('e' | 'E') = self.peek() {
+            is_float = true;
+            self.next();
+            if let Some('+' | '-') = self.peek() {
+                self.next();
+            }
+            if !matches!(self.peek(), Some('0'..='9')) {
+                return Err(self.error("expected digit in exponent"));
+            }
+            self.skip_digits();
+        }
+        let text = &self.source[start..self.offset];
+        match text.parse::<i64>() {
+            Ok(value) if !is_float => Ok(LiteralValue::Integer(value)),
+            _ => Ok(LiteralValue::Float(text.parse::<f64>().unwrap())),
+        }
+    }
+    fn skip_digits(&mut self) {
+        while let Some('0'..='9') = self.peek() {
+            self.next();
+        }
+    }
+}
+
+fn write_value(
+    out: &mut String,
+    value: &LiteralValue,
+    indent: usize,
+    depth: usize,
+) -> Result<(), RuntimeError> {
+    if depth > MAX_DEPTH {
+        return Err(error("Value nested too deeply to serialize.".to_string()));
+    }
+    match value {
+        LiteralValue::None => out.push_str("null"),
+        LiteralValue::Bool(value) => out.push_str(&value.to_string()),
+        LiteralValue::Integer(value) => out.push_str(&value.to_string()),
+        // Debug formatting keeps the fraction, so 1.0 stays a float when parsed back.
+        LiteralValue::Float(value) if value.is_finite() => out.push_str(&format!("{:?}", value)),
+        LiteralValue::Float(value) => {
+            return Err(error(format!("Cannot serialize {} to JSON.", value)))
+        }
+        LiteralValue::String(value) => write_string(out, value),
+        LiteralValue::List(items) => {
+            let items = items.borrow();
+            write_sequence(out, ('[', ']'), items.len(), indent, depth, |out, index| {
+                write_value(out, &items[index], indent, depth + 1)
+            })?;
+        }
+        LiteralValue::Map(entries) => {
+            let entries = entries.borrow();
+            let entries: Vec<_> = entries.iter().collect();
+            let separator = if indent > 0 { ": " } else { ":" };
+            write_sequence(
+                out,
+                ('{', '}'),
+                entries.len(),
+                indent,
+                depth,
+                |out, index| {
+                    let (key, value) = entries[index];
+                    write_string(out, key);
+                    out.push_str(separator);
+                    write_value(out, value, indent, depth + 1)
+                },
+            )?;
+        }
+        LiteralValue::Function(_) | LiteralValue::NativeFunction(_) | LiteralValue::Error(_) => {
+            return Err(error(format!(
+                "Cannot serialize {} to JSON.",
+                value.type_name()
+            )))
+        }
+    }
+    Ok(())
+}
+
+fn write_sequence(
+    out: &mut String,
+    (open, close): (char, char),
+    len: usize,
+    indent: usize,
+    depth: usize,
+    mut write_item: impl FnMut(&mut String, usize) -> Result<(), RuntimeError>,
+) -> Result<(), RuntimeError> {
+    out.push(open);
+    for index in 0..len {
+        if index > 0 {
+            out.push(',');
+        }
+        if indent > 0 {
+            out.push('\n');
+            out.push_str(&" ".repeat(padding(indent, depth + 1)?));
+        }
+        write_item(out, index)?;
+    }
+    if indent > 0 && len > 0 {
+        out.push('\n');
+        out.push_str(&" ".repeat(padding(indent, depth)?));
+    }
+    out.push(close);
+    Ok(())
+}
+
+fn padding(indent: usize, depth: usize) -> Result<usize, RuntimeError> {
+    indent
+        .checked_mul(depth)
+        .ok_or_else(|| error("Result too large.".to_string()))
+}
+
+pub(crate) fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
@@ -0,0 +1,145 @@
+use std::f64::consts;
+
+use super::{error, float_to_int, namespace, native, unexpected_type};
+use crate::common::LiteralValue;
+use crate::convert::arg;
+use crate::error::RuntimeError;
+use crate::function::Arity;
+
+pub(super) fn module() -> LiteralValue {
+    namespace(vec![
+        ("pi", LiteralValue::Float(consts::PI)),
+        ("e", LiteralValue::Float(consts::E)),
+        ("inf", LiteralValue::Float(f64::INFINITY)),
+        ("nan", LiteralValue::Float(f64::NAN)),
+        ("sqrt", float_fn("sqrt", f64::sqrt)),
+        ("exp", float_fn("exp", f64::exp)),
+        ("sin", float_fn("sin", f64::sin)),
+        ("cos", float_fn("cos", f64::cos)),
+        ("tan", float_fn("tan", f64::tan)),
+        ("asin", float_fn("asin", f64::asin)),
+        ("acos", float_fn("acos", f64::acos)),
+        ("atan", float_fn("atan", f64::atan)),
+        ("atan2", native("math.atan2", 2, atan2)),
+        ("log", native("math.log", Arity::Range(1, 2), log)),
+        ("log2", native("math.log2", 1, |args| log_base(args, 2.0))),
+        (
+            "log10",
+            native("math.log10", 1, |args| log_base(args, 10.0)),
+        ),
+        ("pow", native("math.pow", 2, pow)),
+        (
+            "floor",
+            native("math.floor", 1, |args| to_int(args, f64::floor)),
+        ),
+        (
+            "ceil",
+            native("math.ceil", 1, |args| to_int(args, f64::ceil)),
+        ),
+        ("round", native("math.round", Arity::Range(1, 2), round)),
+        ("is_nan", native("math.is_nan", 1, is_nan)),
+        ("gcd", native("math.gcd", 2, gcd)),
+    ])
+}
+
+// A NaN result from a non-NaN input means the input was outside the domain.
+fn checked(input: f64, result: f64) -> Result<LiteralValue, RuntimeError> {
+    if result.is_nan() && !input.is_nan() {
+        Err(error("Math domain error.".to_string()))
+    } else {
+        Ok(LiteralValue::Float(result))
+    }
+}
+
+fn float_fn(name: &str, func: fn(f64) -> f64) -> LiteralValue {
+    native(&format!("math.{name}"), 1, move |args| {
+        let value = arg::<f64>(args, 0)?;
+        checked(value, func(value))
+    })
+}
+
+fn atan2(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let y = arg::<f64>(args, 0)?;
+    let x = arg::<f64>(args, 1)?;
+    Ok(LiteralValue::Float(y.atan2(x)))
+}
+
+fn log_base(args: &[LiteralValue], base: f64) -> Result<LiteralValue, RuntimeError> {
+    let value = arg::<f64>(args, 0)?;
+    if value <= 0.0 || base <= 0.0 || base == 1.0 {
+        return Err(error("Math domain error.".to_string()));
+    }
+    // The dedicated functions are exact where `log` may be off by an ulp.
+    let result = match base {
+        2.0 => value.log2(),
+        10.0 => value.log10(),
+        consts::E => value.ln(),
+        _ => value.log(base),
+    };
+    Ok(LiteralValue::Float(result))
+}
+
+// log(x) is the natural logarithm; log(x, base) uses the given base.
+fn log(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let base = match args.get(1) {
+        Some(_) => arg::<f64>(args, 1)?,
+        None => consts::E,
+    };
+    log_base(args, base)
+}
+
+// Integers raised to non-negative integer powers stay integers.
+fn pow(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    if let (LiteralValue::Integer(base), LiteralValue::Integer(exponent)) = (&args[0], &args[1]) {
+        if *exponent >= 0 {
+            return u32::try_from(*exponent)
+                .ok()
+                .and_then(|exponent| base.checked_pow(exponent))
+                .map(LiteralValue::Integer)
+                .ok_or_else(|| error("Integer overflow.".to_string()));
+        }
+    }
+    let base = arg::<f64>(args, 0)?;
+    let exponent = arg::<f64>(args, 1)?;
+    checked(base, base.powf(exponent))
+}
+
+fn to_int(args: &[LiteralValue], func: fn(f64) -> f64) -> Result<LiteralValue, RuntimeError> {
+    match &args[0] {
+        LiteralValue::Integer(value) => Ok(LiteralValue::Integer(*value)),
+        LiteralValue::Float(value) => float_to_int(func(*value)).map(LiteralValue::Integer),
+        _ => Err(unexpected_type("number", args, 0)),
+    }
+}
+
+// round(x) gives an int, rounding halves away from zero; round(x, digits)
+// gives a float.
+fn round(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    if args.len() == 1 {
+        return to_int(args, f64::round);
+    }
+    let value = arg::<f64>(args, 0)?;
+    let digits = arg::<i64>(args, 1)?;
+    let digits = i32::try_from(digits).map_err(|_| error("Too many digits.".to_string()))?;
+    let scale = 10f64.powi(digits);
+    Ok(LiteralValue::Float((value * scale).round() / scale))
+}
+
+fn is_nan(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    match &args[0] {
+        LiteralValue::Float(value) => Ok(LiteralValue::Bool(value.is_nan())),
+        LiteralValue::Integer(_) => Ok(LiteralValue::Bool(false)),
+        _ => Err(unexpected_type("number", args, 0)),
+    }
+}
+
+fn gcd(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let mut a = arg::<i64>(args, 0)?.unsigned_abs();
+    let mut b = arg::<i64>(args, 1)?.unsigned_abs();
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    i64::try_from(a)
+        .map(LiteralValue::Integer)
+        .map_err(|_| error("Integer overflow.".to_string()))
+}
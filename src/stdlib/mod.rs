@@ -0,0 +1,131 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use crate::common::LiteralValue;
+use crate::convert::ConversionError;
+use crate::error::RuntimeError;
+use crate::function::{Arity, NativeFunction};
+use crate::interpreter::Interpreter;
+
+mod builtins;
+mod fs;
+mod io;
+mod json;
+mod math;
+mod os;
+mod string;
+
+pub(crate) use json::{parse_json, to_json, write_string};
+
+// Selects the parts of the standard library that reach outside the
+// interpreter. Everything is enabled by default; sandboxed embeddings can
+// turn modules off. `imports` lets scripts load other files as modules.
+#[derive(Debug, Clone, Copy)]
+pub struct Capabilities {
+    pub fs: bool,
+    pub os: bool,
+    pub imports: bool,
+}
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities {
+            fs: true,
+            os: true,
+            imports: true,
+        }
+    }
+}
+impl Capabilities {
+    pub fn sandboxed() -> Self {
+        Capabilities {
+            fs: false,
+            os: false,
+            imports: false,
+        }
+    }
+}
+
+// Installs the standard library into the interpreter's globals.
+pub fn register(interpreter: &mut Interpreter, capabilities: Capabilities) {
+    builtins::register(interpreter);
+    interpreter.set_global("math", math::module());
+    interpreter.set_global("json", json::module());
+    string::register(interpreter);
+    if capabilities.fs {
+        interpreter.set_global("fs", fs::module());
+    }
+    if capabilities.os {
+        interpreter.set_global("os", os::module());
+    }
+    let output = interpreter.output();
+    interpreter.set_global("io", io::module(output));
+    // Hosts replace this with the arguments given after the script path.
+    interpreter.set_global("args", Vec::<String>::new());
+}
+
+// Looks up a built-in method on a string or number, bound to that value.
+pub fn method(object: &LiteralValue, name: &str) -> Option<LiteralValue> {
+    match object {
+        LiteralValue::String(value) => string::method(value, name),
+        LiteralValue::Integer(_) | LiteralValue::Float(_) => string::number_method(object, name),
+        _ => None,
+    }
+}
+
+pub fn method_names(object: &LiteralValue) -> &'static [&'static str] {
+    match object {
+        LiteralValue::String(_) => string::METHODS,
+        LiteralValue::Integer(_) | LiteralValue::Float(_) => string::NUMBER_METHODS,
+        _ => &[],
+    }
+}
+
+fn native<F>(name: &str, arity: impl Into<Arity>, func: F) -> LiteralValue
+where
+    F: Fn(&[LiteralValue]) -> Result<LiteralValue, RuntimeError> + 'static,
+{
+    let function = NativeFunction::new(name, arity.into(), Rc::new(func));
+    LiteralValue::NativeFunction(Rc::new(function))
+}
+
+// Modules like `math` are maps of functions and constants read with `.`.
+fn namespace(entries: Vec<(&str, LiteralValue)>) -> LiteralValue {
+    let entries: BTreeMap<String, LiteralValue> = entries
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect();
+    LiteralValue::Map(Rc::new(RefCell::new(entries)))
+}
+
+// Native errors are reported at line 0; the interpreter fills in the call site.
+fn error(message: String) -> RuntimeError {
+    RuntimeError::new(message, 0)
+}
+
+// Natives that build strings (in bytes) or lists bigger than these fail with
+// too_large, which scripts can catch, rather than failing the allocation.
+const MAX_STRING_SIZE: usize = 1 << 30;
+const MAX_LIST_SIZE: usize = 1 << 24;
+
+fn too_large() -> RuntimeError {
+    error("Result too large.".to_string())
+}
+
+fn unexpected_type(expected: &'static str, args: &[LiteralValue], index: usize) -> RuntimeError {
+    ConversionError {
+        expected,
+        found: args[index].type_name(),
+        argument: Some(index),
+    }
+    .into()
+}
+
+// `as` saturates, so range-check floats before truncating them to integers.
+fn float_to_int(value: f64) -> Result<i64, RuntimeError> {
+    if value.is_finite() && value.abs() < i64::MAX as f64 {
+        Ok(value.trunc() as i64)
+    } else {
+        Err(error(format!("Cannot convert {} to int.", value)))
+    }
+}
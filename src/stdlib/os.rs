@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::env;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, Utc};
+
+use super::{error, namespace, native};
+use crate::common::LiteralValue;
+use crate::convert::{arg, IntoValue};
+use crate::error::RuntimeError;
+use crate::function::Arity;
+
+const DEFAULT_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub(super) fn module() -> LiteralValue {
+    // Monotonic readings are relative to when the module was created.
+    let start = Instant::now();
+    namespace(vec![
+        ("getenv", native("os.getenv", 1, getenv)),
+        ("setenv", native("os.setenv", 2, setenv)),
+        ("time", native("os.time", 0, time)),
+        (
+            "monotonic",
+            native("os.monotonic", 0, move |_| {
+                Ok(LiteralValue::Float(start.elapsed().as_secs_f64()))
+            }),
+        ),
+        (
+            "format_time",
+            native("os.format_time", Arity::Range(1, 2), |args| {
+                format_time(args, false)
+            }),
+        ),
+        (
+            "format_utc",
+            native("os.format_utc", Arity::Range(1, 2), |args| {
+                format_time(args, true)
+            }),
+        ),
+        ("sleep", native("os.sleep", 1, sleep)),
+        ("cwd", native("os.cwd", 0, cwd)),
+        (
+            "pid",
+            native(
+                "os.pid",
+                0,
+                |_| Ok((std::process::id() as i64).into_value()),
+            ),
+        ),
+        ("run", native("os.run", Arity::Range(1, 2), run)),
+    ])
+}
+
+// Returns nil for unset variables.
+fn getenv(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let name = arg::<String>(args, 0)?;
+    Ok(env::var(name).ok().into_value())
+}
+
+fn setenv(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let name = arg::<String>(args, 0)?;
+    let value = arg::<String>(args, 1)?;
+    if name.is_empty() || name.contains(['=', '\0']) || value.contains('\0') {
+        return Err(error(format!("Invalid environment variable '{}'.", name)));
+    }
+    env::set_var(name, value);
+    Ok(LiteralValue::None)
+}
+
+// Seconds since the Unix epoch.
+fn time(_args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let now = Utc::now();
+    Ok(LiteralValue::Float(
+        now.timestamp_micros() as f64 / 1_000_000.0,
+    ))
+}
+
+// Formats a Unix timestamp with strftime-style specifiers, in local time or UTC.
+fn format_time(args: &[LiteralValue], utc: bool) -> Result<LiteralValue, RuntimeError> {
+    let timestamp = arg::<f64>(args, 0)?;
+    let format = match args.get(1) {
+        Some(_) => arg::<String>(args, 1)?,
+        None => DEFAULT_TIME_FORMAT.to_string(),
+    };
+    let items: Vec<Item> = StrftimeItems::new(&format).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(error(format!("Invalid time format '{}'.", format)));
+    }
+    let micros = (timestamp * 1_000_000.0).round();
+    let time = DateTime::from_timestamp_micros(micros as i64)
+        .filter(|_| micros.is_finite())
+        .ok_or_else(|| error(format!("Timestamp {} out of range.", timestamp)))?;
+    let formatted = match utc {
+        true => time.format_with_items(items.into_iter()).to_string(),
+        false => time
+            .with_timezone(&Local)
+            .format_with_items(items.into_iter())
+            .to_string(),
+    };
+    Ok(LiteralValue::String(formatted))
+}
+
+fn sleep(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let seconds = arg::<f64>(args, 0)?;
+    let duration = Duration::try_from_secs_f64(seconds)
+        .map_err(|_| error(format!("Invalid sleep duration {}.", seconds)))?;
+    thread::sleep(duration);
+    Ok(LiteralValue::None)
+}
+
+fn cwd(_args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let path = env::current_dir()
+        .map_err(|err| error(format!("Cannot read working directory: {}.", err)))?;
+    Ok(LiteralValue::String(path.to_string_lossy().into_owned()))
+}
+
+// run(program, args) runs a program without a shell and waits for it. The
+// result maps stdout, stderr and code; code is nil if a signal ended it.
+fn run(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let program = arg::<String>(args, 0)?;
+    let arguments = match args.get(1) {
+        Some(_) => arg::<Vec<String>>(args, 1)?,
+        None => Vec::new(),
+    };
+    let output = Command::new(&program)
+        .args(&arguments)
+        .output()
+        .map_err(|err| error(format!("Cannot run '{}': {}.", program, err)))?;
+    let mut result = HashMap::new();
+    result.insert(
+        "stdout".to_string(),
+        String::from_utf8_lossy(&output.stdout).into_value(),
+    );
+    result.insert(
+        "stderr".to_string(),
+        String::from_utf8_lossy(&output.stderr).into_value(),
+    );
+    result.insert(
+        "code".to_string(),
+        output.status.code().map(i64::from).into_value(),
+    );
+    Ok(result.into_value())
+}
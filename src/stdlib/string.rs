@@ -0,0 +1,192 @@
+use super::{error, native, too_large, MAX_STRING_SIZE};
+use crate::common::LiteralValue;
+use crate::convert::{arg, IntoValue};
+use crate::error::RuntimeError;
+use crate::function::Arity;
+use crate::interpreter::Interpreter;
+
+type Method = fn(&str, &[LiteralValue]) -> Result<LiteralValue, RuntimeError>;
+
+pub(super) fn register(interpreter: &mut Interpreter) {
+    interpreter.register_fn("ord", 1, ord);
+    interpreter.register_fn("chr", 1, chr);
+}
+
+// The names `method` and `number_method` accept.
+pub(super) const METHODS: &[&str] = &[
+    "ends_with",
+    "find",
+    "join",
+    "len",
+    "lower",
+    "pad_left",
+    "pad_right",
+    "repeat",
+    "replace",
+    "split",
+    "starts_with",
+    "trim",
+    "upper",
+];
+pub(super) const NUMBER_METHODS: &[&str] = &["to_fixed"];
+
+pub(super) fn method(receiver: &str, name: &str) -> Option<LiteralValue> {
+    let (arity, func): (Arity, Method) = match name {
+        "len" => (Arity::Exact(0), len),
+        "upper" => (Arity::Exact(0), |receiver, _| {
+            string(receiver.to_uppercase())
+        }),
+        "lower" => (Arity::Exact(0), |receiver, _| {
+            string(receiver.to_lowercase())
+        }),
+        "trim" => (Arity::Exact(0), |receiver, _| string(receiver.trim())),
+        "split" => (Arity::Range(0, 1), split),
+        "join" => (Arity::Exact(1), join),
+        "replace" => (Arity::Exact(2), replace),
+        "find" => (Arity::Exact(1), find),
+        "starts_with" => (Arity::Exact(1), |receiver, args| {
+            Ok(LiteralValue::Bool(
+                receiver.starts_with(&arg::<String>(args, 0)?),
+            ))
+        }),
+        "ends_with" => (Arity::Exact(1), |receiver, args| {
+            Ok(LiteralValue::Bool(
+                receiver.ends_with(&arg::<String>(args, 0)?),
+            ))
+        }),
+        "repeat" => (Arity::Exact(1), repeat),
+        "pad_left" => (Arity::Range(1, 2), |receiver, args| {
+            pad(receiver, args, true)
+        }),
+        "pad_right" => (Arity::Range(1, 2), |receiver, args| {
+            pad(receiver, args, false)
+        }),
+        _ => return None,
+    };
+    let receiver = receiver.to_string();
+    Some(native(name, arity, move |args| func(&receiver, args)))
+}
+
+pub(super) fn number_method(receiver: &LiteralValue, name: &str) -> Option<LiteralValue> {
+    match name {
+        "to_fixed" => {
+            let receiver = receiver.clone();
+            Some(native(name, 1, move |args| to_fixed(&receiver, args)))
+        }
+        _ => None,
+    }
+}
+
+fn string(value: impl Into<String>) -> Result<LiteralValue, RuntimeError> {
+    Ok(LiteralValue::String(value.into()))
+}
+
+fn len(receiver: &str, _args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    Ok(LiteralValue::Integer(receiver.chars().count() as i64))
+}
+
+// With no separator, splits on runs of whitespace.
+fn split(receiver: &str, args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let parts: Vec<String> = match args.first() {
+        Some(_) => {
+            let separator = arg::<String>(args, 0)?;
+            if separator.is_empty() {
+                return Err(error("Separator must not be empty.".to_string()));
+            }
+            receiver.split(&separator).map(str::to_string).collect()
+        }
+        None => receiver.split_whitespace().map(str::to_string).collect(),
+    };
+    Ok(parts.into_value())
+}
+
+fn join(receiver: &str, args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let items = arg::<Vec<LiteralValue>>(args, 0)?;
+    let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+    string(items.join(receiver))
+}
+
+fn replace(receiver: &str, args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let from = arg::<String>(args, 0)?;
+    let to = arg::<String>(args, 1)?;
+    string(receiver.replace(&from, &to))
+}
+
+// Returns the character index of the first match, or -1.
+fn find(receiver: &str, args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let needle = arg::<String>(args, 0)?;
+    let index = match receiver.find(&needle) {
+        Some(offset) => receiver[..offset].chars().count() as i64,
+        None => -1,
+    };
+    Ok(LiteralValue::Integer(index))
+}
+
+fn repeat(receiver: &str, args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let count = arg::<i64>(args, 0)?;
+    let count =
+        usize::try_from(count).map_err(|_| error("Count must not be negative.".to_string()))?;
+    receiver
+        .len()
+        .checked_mul(count)
+        .filter(|&size| size <= MAX_STRING_SIZE)
+        .ok_or_else(too_large)?;
+    string(receiver.repeat(count))
+}
+
+// Pads to `width` characters with `fill`, a space unless given.
+fn pad(receiver: &str, args: &[LiteralValue], left: bool) -> Result<LiteralValue, RuntimeError> {
+    let width = arg::<i64>(args, 0)?;
+    let fill = match args.get(1) {
+        Some(_) => single_char(&arg::<String>(args, 1)?)?,
+        None => ' ',
+    };
+    let length = receiver.chars().count();
+    let count = usize::try_from(width.max(0))
+        .map_err(|_| too_large())?
+        .saturating_sub(length);
+    fill.len_utf8()
+        .checked_mul(count)
+        .and_then(|size| size.checked_add(receiver.len()))
+        .filter(|&size| size <= MAX_STRING_SIZE)
+        .ok_or_else(too_large)?;
+    let padding: String = std::iter::repeat_n(fill, count).collect();
+    match left {
+        true => string(padding + receiver),
+        false => string(receiver.to_string() + &padding),
+    }
+}
+
+fn to_fixed(receiver: &LiteralValue, args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let value = arg::<f64>(std::slice::from_ref(receiver), 0)?;
+    let digits = arg::<i64>(args, 0)?;
+    if !(0..=100).contains(&digits) {
+        return Err(error("Digits must be between 0 and 100.".to_string()));
+    }
+    string(format!("{:.*}", digits as usize, value))
+}
+
+fn single_char(value: &str) -> Result<char, RuntimeError> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(error(format!(
+            "Expected a single character, found string of length {}.",
+            value.chars().count()
+        ))),
+    }
+}
+
+fn ord(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let c = single_char(&arg::<String>(args, 0)?)?;
+    Ok(LiteralValue::Integer(c as i64))
+}
+
+fn chr(args: &[LiteralValue]) -> Result<LiteralValue, RuntimeError> {
+    let code = arg::<i64>(args, 0)?;
+    u32::try_from(code)
+        .ok()
+        .and_then(char::from_u32)
+        .map(|c| LiteralValue::String(c.to_string()))
+        .ok_or_else(|| error(format!("Invalid code point {}.", code)))
+}
@@ -0,0 +1,42 @@
+use ownpy::Engine;
+
+fn eval(source: &str) -> Result<String, String> {
+    let mut engine = Engine::new();
+    match engine.eval(source) {
+        Ok(value) => Ok(value.to_string()),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+#[test]
+fn range_counts_up_and_down() {
+    assert_eq!(eval("range(4);").unwrap(), "[0, 1, 2, 3]");
+    assert_eq!(eval("range(2, 10, 3);").unwrap(), "[2, 5, 8]");
+    assert_eq!(eval("range(10, 2, -3);").unwrap(), "[10, 7, 4]");
+    assert_eq!(eval("range(5, 1);").unwrap(), "[]");
+    assert_eq!(
+        eval("range(-9223372036854775808, 9223372036854775807, 9223372036854775807);").unwrap(),
+        "[-9223372036854775808, -1, 9223372036854775806]"
+    );
+}
+
+#[test]
+fn huge_ranges_are_catchable_errors() {
+    for args in [
+        "9000000000000000000",
+        "0, 100000000",
+        "0, -9000000000000000000, -1",
+    ] {
+        let source = format!(
+            "var message; try {{ range({}); }} catch (e) {{ message = e; }} message;",
+            args
+        );
+        assert_eq!(eval(&source).unwrap(), "range(): Result too large.");
+    }
+}
+
+#[test]
+fn range_rejects_a_zero_step() {
+    let error = eval("range(1, 2, 0);").unwrap_err();
+    assert!(error.contains("Step must not be zero."), "{}", error);
+}
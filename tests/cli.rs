@@ -0,0 +1,212 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+fn ownpy(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ownpy"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Commands that don't read stdin may exit before it's written.
+    let _ = child.stdin.take().unwrap().write_all(stdin.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+// A file holding `source`, unique to each test.
+fn script(name: &str, source: &str) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("ownpy-cli-{}-{}.ownpy", name, std::process::id()));
+    fs::write(&path, source).unwrap();
+    path
+}
+
+#[test]
+fn running_a_script_exits_zero() {
+    let path = script("run", "print args;\n");
+    let output = ownpy(&[path.to_str().unwrap(), "a", "-b"], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "[a, -b]\n");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn scripts_can_be_read_from_stdin() {
+    let output = ownpy(&["-"], "print 1 + 2;\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "3\n");
+}
+
+#[test]
+fn usage_errors_exit_64() {
+    for args in [&["--no-such-flag"][..], &["--check"], &["fmt", "--bogus"]] {
+        let output = ownpy(args, "");
+        assert_eq!(output.status.code(), Some(64), "{:?}", args);
+    }
+}
+
+#[test]
+fn help_exits_zero() {
+    let output = ownpy(&["--help"], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains("Usage:"));
+}
+
+#[test]
+fn syntax_errors_exit_65() {
+    let output = ownpy(&["-e", "print (;"], "");
+    assert_eq!(output.status.code(), Some(65));
+    assert!(stderr(&output).contains("Expect expression."));
+}
+
+#[test]
+fn missing_scripts_exit_66() {
+    let output = ownpy(&["/no/such/script.ownpy"], "");
+    assert_eq!(output.status.code(), Some(66));
+    assert!(stderr(&output).contains("cannot open '/no/such/script.ownpy'"));
+}
+
+#[test]
+fn runtime_errors_exit_70() {
+    let output = ownpy(&["-e", "print 1 / nil;"], "");
+    assert_eq!(output.status.code(), Some(70));
+    assert!(stderr(&output).contains("Traceback"));
+}
+
+#[test]
+fn exit_sets_the_code() {
+    let output = ownpy(&["-e", "exit(3);"], "");
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn unreadable_stdin_exits_74() {
+    // Reading a directory fails once the read starts.
+    let output = Command::new(env!("CARGO_BIN_EXE_ownpy"))
+        .arg("-")
+        .stdin(File::open(std::env::temp_dir()).unwrap())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(74));
+    assert!(stderr(&output).contains("cannot read stdin"));
+}
+
+#[test]
+fn check_passes_valid_scripts_without_running_them() {
+    let source = "fun f() {\n  return later;\n}\nvar later = 1;\nprint f() + len(args);\n";
+    let output = ownpy(&["--check", "-e", source], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).is_empty());
+}
+
+#[test]
+fn check_reports_undefined_names() {
+    let output = ownpy(
+        &["--check", "-e", "print 1;\nmissing = 2;\nprint other;"],
+        "",
+    );
+    assert_eq!(output.status.code(), Some(65));
+    assert_eq!(
+        stderr(&output),
+        "[line 2] Error at 'missing': Undefined variable 'missing'.\n\
+         [line 3] Error at 'other': Undefined variable 'other'.\n"
+    );
+}
+
+#[test]
+fn check_reports_syntax_errors() {
+    let output = ownpy(&["--check", "-e", "var = 1;"], "");
+    assert_eq!(output.status.code(), Some(65));
+}
+
+#[test]
+fn fmt_check_exits_1_for_unformatted_files() {
+    let messy = script("fmt-messy", "print   1;\n");
+    let tidy = script("fmt-tidy", "print 1;\n");
+    let output = ownpy(
+        &[
+            "fmt",
+            "--check",
+            messy.to_str().unwrap(),
+            tidy.to_str().unwrap(),
+        ],
+        "",
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), format!("{}\n", messy.display()));
+    assert_eq!(fs::read_to_string(&messy).unwrap(), "print   1;\n");
+    let output = ownpy(&["fmt", "--check", tidy.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(0));
+    fs::remove_file(messy).unwrap();
+    fs::remove_file(tidy).unwrap();
+}
+
+#[test]
+fn fmt_rewrites_files_and_formats_stdin() {
+    let path = script("fmt-write", "print   1;\n");
+    let output = ownpy(&["fmt", path.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "print 1;\n");
+    fs::remove_file(path).unwrap();
+    let output = ownpy(&["fmt"], "var  a=1;\n");
+    assert_eq!(stdout(&output), "var a = 1;\n");
+    let output = ownpy(&["fmt", "-"], "print (;\n");
+    assert_eq!(output.status.code(), Some(65));
+}
+
+#[test]
+fn fmt_missing_files_exit_66() {
+    let output = ownpy(&["fmt", "/no/such/script.ownpy"], "");
+    assert_eq!(output.status.code(), Some(66));
+}
+
+#[test]
+fn lint_exits_1_on_warnings() {
+    let output = ownpy(&["lint"], "var unused = 1;\n");
+    assert_eq!(output.status.code(), Some(1));
+    let output = ownpy(&["lint"], "print 1;\n");
+    assert_eq!(output.status.code(), Some(0));
+    let output = ownpy(&["lint"], "print (;\n");
+    assert_eq!(output.status.code(), Some(65));
+}
+
+#[test]
+fn lsp_bad_frames_exit_74() {
+    let output = ownpy(&["lsp"], "Content-Length: 99999999999999\r\n\r\n");
+    assert_eq!(output.status.code(), Some(74));
+}
+
+#[test]
+fn dump_ast_prints_the_tree_without_running() {
+    let output = ownpy(&["--dump-ast", "-e", "print 1 + 2;"], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "(print (+ 1 2))\n");
+    let output = ownpy(&["--dump-ast=json", "-e", "x;"], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        stdout(&output),
+        "[\n  {\n    \"type\": \"Expr\",\n    \"expr\": {\n      \"type\": \"Var\",\n      \"name\": \"x\",\n      \"line\": 1\n    }\n  }\n]\n"
+    );
+}
+
+#[test]
+fn dump_ast_reports_parse_errors() {
+    let output = ownpy(&["--dump-ast", "-e", "print (;"], "");
+    assert_eq!(output.status.code(), Some(65));
+    assert_eq!(stdout(&output), "");
+    assert!(stderr(&output).contains("Expect expression."));
+    let output = ownpy(&["--dump-ast=xml", "-e", "1;"], "");
+    assert_eq!(output.status.code(), Some(64));
+    assert!(stderr(&output).contains("invalid value 'xml'"));
+}
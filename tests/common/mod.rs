@@ -0,0 +1,48 @@
+// Helpers shared by the integration tests that run scripts in-process.
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use ownpy::{Engine, Error};
+
+// Collects what a script prints so tests can read it back.
+#[derive(Clone, Default)]
+pub struct Output(Rc<RefCell<Vec<u8>>>);
+
+impl Output {
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub fn engine() -> (Engine, Output) {
+    let mut engine = Engine::new();
+    let output = Output::default();
+    engine.set_output(output.clone());
+    (engine, output)
+}
+
+// What the script printed, or the error it stopped with.
+pub fn run(source: &str) -> Result<String, Error> {
+    let (mut engine, output) = engine();
+    engine.eval(source).map(|_| output.text())
+}
+
+pub fn error(source: &str) -> String {
+    match run(source) {
+        Ok(output) => panic!("expected an error, got output {:?}", output),
+        Err(error) => error.to_string(),
+    }
+}
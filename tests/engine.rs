@@ -0,0 +1,110 @@
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ownpy::{
+    Capabilities, Engine, Error, Interpreter, Parser, Reporter, Scanner, Statement, Value,
+};
+
+#[test]
+fn eval_returns_the_trailing_expression() {
+    let mut engine = Engine::new();
+    assert_eq!(engine.eval("1 + 2;").unwrap(), Value::Integer(3));
+    assert_eq!(engine.eval("var x = 1;").unwrap(), Value::None);
+    assert_eq!(engine.eval("print 1;").unwrap(), Value::None);
+}
+
+#[test]
+fn globals_persist_between_evals() {
+    let (mut engine, output) = common::engine();
+    engine
+        .eval("var greeting = \"hi\"; fun shout(s) { return s + \"!\"; }")
+        .unwrap();
+    assert_eq!(
+        engine.eval("shout(greeting);").unwrap(),
+        Value::String("hi!".to_string())
+    );
+    engine.eval("print greeting;").unwrap();
+    assert_eq!(output.text(), "hi\n");
+}
+
+#[test]
+fn eval_separates_error_kinds() {
+    let mut engine = Engine::new();
+    match engine.eval("var s = \"open;") {
+        Err(Error::Scan(errors)) => assert_eq!(errors[0].message, "Unterminated string."),
+        other => panic!("expected a scan error, got {:?}", other.map(|_| ())),
+    }
+    match engine.eval("var = 1;") {
+        Err(Error::Parse(errors)) => assert_eq!(errors[0].message, "Expected variable name"),
+        other => panic!("expected a parse error, got {:?}", other.map(|_| ())),
+    }
+    match engine.eval("missing;") {
+        Err(Error::Runtime(error)) => {
+            assert_eq!(error.message, "Undefined variable 'missing'.");
+            assert_eq!(error.line, 1);
+        }
+        other => panic!("expected a runtime error, got {:?}", other.map(|_| ())),
+    }
+    assert!(matches!(engine.eval("exit(3);"), Err(Error::Exit(3))));
+}
+
+#[test]
+fn a_failed_eval_leaves_the_engine_usable() {
+    let mut engine = Engine::new();
+    engine.eval("var kept = 1;").unwrap();
+    assert!(engine.eval("kept = missing;").is_err());
+    assert_eq!(engine.eval("kept;").unwrap(), Value::Integer(1));
+}
+
+#[derive(Clone, Default)]
+struct Collect(Rc<RefCell<Vec<String>>>);
+
+impl Reporter for Collect {
+    fn report(&mut self, error: &Error) {
+        self.0.borrow_mut().push(error.to_string());
+    }
+}
+
+#[test]
+fn the_reporter_sees_errors_but_not_exits() {
+    let mut engine = Engine::new();
+    let reported = Collect::default();
+    engine.set_reporter(reported.clone());
+    assert!(engine.eval("1 +;").is_err());
+    assert!(engine.eval("exit(0);").is_err());
+    engine.eval("1;").unwrap();
+    assert_eq!(
+        *reported.0.borrow(),
+        vec!["[line 1] Error at ';': Expect expression.".to_string()]
+    );
+}
+
+#[test]
+fn sandboxed_engines_have_no_host_access() {
+    let mut engine = Engine::with_capabilities(Capabilities::sandboxed());
+    for name in ["fs", "os"] {
+        let error = engine.eval(&format!("{};", name)).unwrap_err().to_string();
+        assert!(
+            error.ends_with(&format!("Undefined variable '{}'.", name)),
+            "{}",
+            error
+        );
+    }
+    assert_eq!(engine.eval("len(\"abc\");").unwrap(), Value::Integer(3));
+}
+
+#[test]
+fn the_pipeline_stages_are_usable_on_their_own() {
+    let tokens = Scanner::new("var x = 2; x * 21;".to_string())
+        .scan_tokens()
+        .unwrap();
+    let statements = Parser::new(tokens).parse().unwrap();
+    assert!(matches!(statements[0], Statement::Var { .. }));
+    let mut interpreter = Interpreter::default();
+    assert_eq!(
+        interpreter.interpret(&statements).unwrap(),
+        Value::Integer(42)
+    );
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use common::{error, run};
+
+#[test]
+fn thrown_values_are_caught() {
+    let source = "
+        try { throw \"boom\"; } catch (e) { print e; } finally { print \"finally\"; }
+        try { throw 42; } catch (e) { print e + 1; }
+    ";
+    assert_eq!(run(source).unwrap(), "boom\nfinally\n43\n");
+}
+
+#[test]
+fn runtime_errors_are_catchable() {
+    let source = "
+        try {
+            print missing;
+        } catch (e) {
+            print type(e);
+            print e.message;
+            print e.line;
+        }
+        try { 1 + \"a\"; } catch (e) { print e.message; }
+    ";
+    assert_eq!(
+        run(source).unwrap(),
+        "error\nUndefined variable 'missing'.\n3\nUnsupported operand types for '+': int and string.\n"
+    );
+}
+
+#[test]
+fn finally_runs_on_every_exit() {
+    let source = "
+        fun f() { try { return 1; } finally { print \"cleanup\"; } }
+        print f();
+        try {
+            try { throw \"inner\"; } finally { print \"unwinding\"; }
+        } catch (e) {
+            print \"caught \" + e;
+        }
+    ";
+    assert_eq!(
+        run(source).unwrap(),
+        "cleanup\n1\nunwinding\ncaught inner\n"
+    );
+}
+
+#[test]
+fn uncaught_exceptions_report_a_traceback() {
+    let source = "fun inner() { throw \"deep\"; }\nfun outer() { inner(); }\nouter();";
+    assert_eq!(
+        error(source),
+        "Traceback (most recent call last):\n  line 3, in <script>\n  line 2, in outer\n  line 1, in inner\nError: deep"
+    );
+}
+
+#[test]
+fn rethrown_exceptions_escape() {
+    let source = "try { throw \"x\"; } catch (e) { throw \"rethrown \" + e; }";
+    assert!(error(source).ends_with("Error: rethrown x"));
+}
+
+#[test]
+fn malformed_handlers_are_syntax_errors() {
+    assert_eq!(
+        error("try { print 1; }"),
+        "[line 1] Error at end: Expect 'catch' or 'finally' after try block."
+    );
+    assert_eq!(error("throw;"), "[line 1] Error at ';': Expect expression.");
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use ownpy::{Engine, Value};
+
+#[test]
+fn host_values_are_visible_to_scripts() {
+    let mut engine = Engine::new();
+    let interpreter = engine.interpreter();
+    interpreter.set_global("limit", 10i64);
+    interpreter.set_global("ratio", 0.5);
+    interpreter.set_global("verbose", true);
+    interpreter.set_global("name", "ownpy");
+    interpreter.set_global("ports", vec![80i64, 443]);
+    interpreter.set_global("labels", HashMap::from([("env".to_string(), "prod")]));
+    interpreter.set_global("missing", None::<i64>);
+    let source = "
+        str(limit) + \" \" + str(ratio) + \" \" + str(verbose) + \" \" + name + \" \"
+            + str(ports[1]) + \" \" + labels.env + \" \" + str(missing);
+    ";
+    assert_eq!(
+        engine.eval(source).unwrap(),
+        Value::String("10 0.5 true ownpy 443 prod nil".to_string())
+    );
+}
+
+#[test]
+fn script_results_convert_back() {
+    let mut engine = Engine::new();
+    engine.interpreter().set_global("doc", r#"{"port": 8080}"#);
+    let source = "
+        var total = 6 * 7;
+        var items = range(3);
+        var config = json.parse(doc);
+        var empty = nil;
+    ";
+    engine.eval(source).unwrap();
+    let interpreter = engine.interpreter();
+    assert_eq!(interpreter.get_global::<i64>("total").unwrap(), Some(42));
+    assert_eq!(interpreter.get_global::<f64>("total").unwrap(), Some(42.0));
+    assert_eq!(
+        interpreter.get_global::<Vec<i64>>("items").unwrap(),
+        Some(vec![0, 1, 2])
+    );
+    assert_eq!(
+        interpreter
+            .get_global::<HashMap<String, i64>>("config")
+            .unwrap(),
+        Some(HashMap::from([("port".to_string(), 8080)]))
+    );
+    assert_eq!(
+        interpreter.get_global::<Option<i64>>("empty").unwrap(),
+        Some(None)
+    );
+    assert_eq!(interpreter.get_global::<i64>("undefined").unwrap(), None);
+}
+
+#[test]
+fn mismatched_types_are_conversion_errors() {
+    let mut engine = Engine::new();
+    engine
+        .eval("var name = \"ownpy\"; var items = range(2);")
+        .unwrap();
+    let interpreter = engine.interpreter();
+    let error = interpreter.get_global::<i64>("name").unwrap_err();
+    assert_eq!(error.to_string(), "Expected int, found string.");
+    let error = interpreter.get_global::<Vec<String>>("items").unwrap_err();
+    assert_eq!(error.to_string(), "Expected string, found int.");
+}
+
+#[test]
+fn set_global_updates_script_variables() {
+    let mut engine = Engine::new();
+    engine
+        .eval("var count = 1; fun current() { return count; }")
+        .unwrap();
+    engine.interpreter().set_global("count", 5i64);
+    assert_eq!(engine.eval("current();").unwrap(), Value::Integer(5));
+}
+
+#[test]
+fn host_globals_survive_a_reset() {
+    let mut engine = Engine::new();
+    engine.interpreter().set_global("host", "kept");
+    engine.eval("var script = 1;").unwrap();
+    engine.interpreter().reset();
+    assert_eq!(
+        engine.interpreter().get_global::<String>("host").unwrap(),
+        Some("kept".to_string())
+    );
+    assert_eq!(
+        engine.interpreter().get_global::<i64>("script").unwrap(),
+        None
+    );
+}
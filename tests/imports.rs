@@ -0,0 +1,37 @@
+use ownpy::{Capabilities, Engine, Error};
+use std::fs;
+use std::path::PathBuf;
+
+// A directory holding `util.ownpy`, unique to each test.
+fn module_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ownpy-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("util.ownpy"), "var answer = 42;\n").unwrap();
+    dir
+}
+
+fn import(capabilities: Capabilities, dir: &PathBuf) -> Result<ownpy::Value, Error> {
+    let mut engine = Engine::with_capabilities(capabilities);
+    engine.interpreter().add_search_path(dir);
+    engine.eval("import util; util.answer;")
+}
+
+#[test]
+fn imports_load_modules_by_default() {
+    let dir = module_dir("imports-default");
+    let value = import(Capabilities::default(), &dir).unwrap();
+    assert_eq!(value.to_string(), "42");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn sandboxed_imports_find_no_modules() {
+    let dir = module_dir("imports-sandboxed");
+    let error = import(Capabilities::sandboxed(), &dir).unwrap_err();
+    assert!(
+        error.to_string().contains("No module named 'util'."),
+        "{}",
+        error
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
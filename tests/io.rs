@@ -0,0 +1,130 @@
+mod common;
+
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use ownpy::Capabilities;
+
+// An empty directory unique to each test, visible to scripts as `dir`.
+fn scratch(name: &str) -> (ownpy::Engine, common::Output, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("ownpy-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let (mut engine, output) = common::engine();
+    engine
+        .interpreter()
+        .set_global("dir", dir.to_string_lossy().into_owned());
+    (engine, output, dir)
+}
+
+#[test]
+fn files_are_written_read_and_removed() {
+    let (mut engine, output, dir) = scratch("io-files");
+    let source = "
+        var path = dir + \"/notes.txt\";
+        print fs.exists(path);
+        fs.write_file(path, \"one\");
+        fs.append_file(path, \" two\");
+        print fs.read_file(path);
+        fs.write_file(dir + \"/lines.txt\", \"a\");
+        fs.append_file(dir + \"/lines.txt\", \"\nb\n\");
+        print fs.read_lines(dir + \"/lines.txt\");
+        print fs.list_dir(dir);
+        fs.remove(path);
+        print fs.exists(path);
+    ";
+    engine.eval(source).unwrap();
+    assert_eq!(
+        output.text(),
+        "false\none two\n[a, b]\n[lines.txt, notes.txt]\nfalse\n"
+    );
+    assert!(!dir.join("notes.txt").exists());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn io_failures_are_catchable() {
+    let (mut engine, output, dir) = scratch("io-failures");
+    let source = "
+        try { fs.read_file(dir + \"/missing.txt\"); } catch (e) { print e.message; }
+        try { fs.list_dir(dir + \"/missing\"); } catch (e) { print e.message; }
+        try { fs.remove(dir + \"/missing.txt\"); } catch (e) { print e.message; }
+    ";
+    engine.eval(source).unwrap();
+    let text = output.text();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 3, "{}", text);
+    assert!(
+        lines[0].starts_with("fs.read_file(): Cannot read '"),
+        "{}",
+        text
+    );
+    assert!(
+        lines[1].starts_with("fs.list_dir(): Cannot list '"),
+        "{}",
+        text
+    );
+    assert!(
+        lines[2].starts_with("fs.remove(): Cannot remove '"),
+        "{}",
+        text
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn sandboxed_engines_have_no_fs() {
+    let mut engine = ownpy::Engine::with_capabilities(Capabilities::sandboxed());
+    let error = engine.eval("fs.exists(\".\");").unwrap_err().to_string();
+    assert!(error.ends_with("Undefined variable 'fs'."), "{}", error);
+}
+
+fn ownpy(args: &[&str], stdin: &str) -> std::process::Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ownpy"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn stdin_is_read_line_by_line() {
+    let source = "print io.read_line(); print io.read_lines(); print io.read_line();";
+    let output = ownpy(&["-e", source], "first\r\nsecond\nthird\n");
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "first\n[second, third]\nnil\n"
+    );
+}
+
+#[test]
+fn eprint_writes_to_stderr() {
+    let output = ownpy(&["-e", "io.write(\"out\"); io.eprint(\"err\");"], "");
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "out");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "err\n");
+}
+
+#[test]
+fn script_arguments_are_in_args() {
+    let dir = std::env::temp_dir().join(format!("ownpy-io-args-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("args.ownpy");
+    fs::write(&script, "print args;\n").unwrap();
+    let output = ownpy(&[script.to_str().unwrap(), "one", "--two"], "");
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "[one, --two]\n");
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,78 @@
+use ownpy::Engine;
+
+// Scripts can't write quotes inside strings, so documents come in as `doc`.
+fn eval(doc: &str, source: &str) -> Result<String, String> {
+    let mut engine = Engine::new();
+    engine.interpreter().set_global("doc", doc);
+    match engine.eval(source) {
+        Ok(value) => Ok(value.to_string()),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+#[test]
+fn documents_round_trip() {
+    let doc = r#"{"a":[1,2.5,"x\n\"y\"",true,false,null],"b":{},"c":[]}"#;
+    let output = eval(doc, "json.stringify(json.parse(doc));").unwrap();
+    assert_eq!(output, doc);
+}
+
+#[test]
+fn parsed_values_are_script_values() {
+    let doc = r#"{"name": "ownpy", "tags": ["a", "b"], "size": 3}"#;
+    let source = "var v = json.parse(doc); v.name + str(len(v.tags) + v.size) + v.tags[1];";
+    assert_eq!(eval(doc, source).unwrap(), "ownpy5b");
+}
+
+#[test]
+fn indent_puts_items_on_their_own_lines() {
+    let output = eval(
+        r#"{"a":[1,{}],"b":[]}"#,
+        "json.stringify(json.parse(doc), 2);",
+    )
+    .unwrap();
+    assert_eq!(
+        output,
+        "{\n  \"a\": [\n    1,\n    {}\n  ],\n  \"b\": []\n}"
+    );
+}
+
+#[test]
+fn indents_outside_the_bounds_are_catchable_errors() {
+    for indent in ["-1", "11", "3000000000", "9000000000000000000"] {
+        let source = format!(
+            "var message; try {{ json.stringify(json.parse(doc), {}); }} catch (e) {{ message = e; }} message;",
+            indent
+        );
+        let output = eval("[1]", &source).unwrap();
+        assert_eq!(output, "json.stringify(): Indent must be between 0 and 10.");
+    }
+    assert_eq!(
+        eval("[1]", "json.stringify(json.parse(doc), 10);").unwrap(),
+        "[\n          1\n]"
+    );
+}
+
+#[test]
+fn parse_errors_give_the_position() {
+    let cases = [
+        ("[1, 2", "line 1, column 6"),
+        ("{\"a\" 1}", "line 1, column 6"),
+        (
+            "[1]\n x",
+            "line 2, column 2: unexpected trailing characters",
+        ),
+        ("", "line 1, column 1"),
+    ];
+    for (doc, position) in cases {
+        let error = eval(doc, "json.parse(doc);").unwrap_err();
+        assert!(error.contains("Invalid JSON"), "{}", error);
+        assert!(error.contains(position), "{}: {}", doc, error);
+    }
+}
+
+#[test]
+fn functions_cannot_be_serialized() {
+    let error = eval("", "json.stringify(clock);").unwrap_err();
+    assert!(error.contains("Cannot serialize"), "{}", error);
+}
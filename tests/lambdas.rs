@@ -0,0 +1,70 @@
+mod common;
+
+use common::{error, run};
+
+#[test]
+fn fun_expressions_are_callable_values() {
+    let source = "
+        var add = fun (a, b) { return a + b; };
+        print add(1, 2);
+        print (fun () { return \"called\"; })();
+        print type(add);
+    ";
+    assert_eq!(run(source).unwrap(), "3\ncalled\nfunction\n");
+}
+
+#[test]
+fn arrow_lambdas_return_their_expression() {
+    let source = "
+        var inc = |x| x + 1;
+        print inc(4);
+        var zero = || 0;
+        print zero();
+        var scaled = |x, by = 10| x * by;
+        print scaled(2);
+    ";
+    assert_eq!(run(source).unwrap(), "5\n0\n20\n");
+}
+
+#[test]
+fn lambdas_capture_their_environment() {
+    let source = "
+        fun counter() {
+            var n = 0;
+            return fun () { n = n + 1; return n; };
+        }
+        var first = counter();
+        var second = counter();
+        first();
+        first();
+        print first();
+        print second();
+        fun apply(f, value) { return f(value); }
+        var offset = 100;
+        print apply(|x| x + offset, 1);
+    ";
+    assert_eq!(run(source).unwrap(), "3\n1\n101\n");
+}
+
+#[test]
+fn lambda_arity_is_checked() {
+    assert!(error("var f = |x| x; f(1, 2);")
+        .contains("lambda() takes 1 positional arguments but 2 were given."));
+}
+
+#[test]
+fn malformed_lambdas_are_syntax_errors() {
+    let first = |source| error(source).lines().next().unwrap().to_string();
+    assert_eq!(
+        first("var f = fun { return 1; };"),
+        "[line 1] Error at '{': Expect '(' after 'fun'."
+    );
+    assert_eq!(
+        first("var f = fun (a) return a;"),
+        "[line 1] Error at 'return': Expect '{' before lambda body."
+    );
+    assert_eq!(
+        first("var f = |a, | a;"),
+        "[line 1] Error at '|': Expect parameter name."
+    );
+}
@@ -0,0 +1,61 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn lint(args: &[&str], source: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ownpy"))
+        .arg("lint")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(source.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+const SHADOWING: &str = "var a = 1;\n{\n  var a = 2;\n  print a;\n}\nprint a;\n";
+
+#[test]
+fn unused_top_level_variables_are_reported() {
+    let output = lint(&[], "var unused = 1;\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "<stdin>: [line 1] Warning: 'unused' is never used. [unused-variable]\n"
+    );
+}
+
+#[test]
+fn clean_scripts_exit_zero() {
+    let output = lint(&[], SHADOWING);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn enable_turns_on_a_rule() {
+    let output = lint(&["--enable", "shadowed-variable"], SHADOWING);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[line 3]"), "{}", stdout);
+    assert!(stdout.contains("[shadowed-variable]"), "{}", stdout);
+}
+
+#[test]
+fn disable_turns_off_a_rule() {
+    let output = lint(&["--disable", "unused-variable", "-"], "var unused = 1;\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn unknown_rules_are_usage_errors() {
+    let output = lint(&["--enable", "no-such-rule"], "");
+    assert_eq!(output.status.code(), Some(64));
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::{error, run};
+
+#[test]
+fn break_leaves_the_innermost_loop() {
+    let source = "
+        for (var i = 0; i < 3; i = i + 1) {
+            var j = 0;
+            while (true) {
+                if (j == 2) break;
+                print str(i) + str(j);
+                j = j + 1;
+            }
+            if (i == 1) break;
+        }
+        print \"done\";
+    ";
+    assert_eq!(run(source).unwrap(), "00\n01\n10\n11\ndone\n");
+}
+
+#[test]
+fn continue_still_runs_the_increment() {
+    let source = "
+        for (var i = 0; i < 5; i = i + 1) {
+            if (i == 1 or i == 3) continue;
+            print i;
+        }
+    ";
+    assert_eq!(run(source).unwrap(), "0\n2\n4\n");
+}
+
+#[test]
+fn continue_in_while_rechecks_the_condition() {
+    let source = "
+        var i = 0;
+        while (i < 4) {
+            i = i + 1;
+            if (i == 2) continue;
+            print i;
+        }
+    ";
+    assert_eq!(run(source).unwrap(), "1\n3\n4\n");
+}
+
+#[test]
+fn break_inside_try_runs_finally() {
+    let source = "
+        while (true) {
+            try { break; } finally { print \"finally\"; }
+        }
+        print \"after\";
+    ";
+    assert_eq!(run(source).unwrap(), "finally\nafter\n");
+}
+
+#[test]
+fn loop_exits_outside_a_loop_are_syntax_errors() {
+    assert_eq!(
+        error("break;"),
+        "[line 1] Error at 'break': Can't use 'break' outside of a loop."
+    );
+    // Only the misplaced keyword is reported; parsing carries on after it.
+    assert_eq!(
+        error("fun f() { continue; }\nprint 1;"),
+        "[line 1] Error at 'continue': Can't use 'continue' outside of a loop."
+    );
+    // A function body inside a loop is not in the loop.
+    assert_eq!(
+        error("while (true) {\n  fun f() { break; }\n  break;\n}"),
+        "[line 2] Error at 'break': Can't use 'break' outside of a loop."
+    );
+}
+
+#[test]
+fn loop_exits_need_a_semicolon() {
+    assert!(error("while (true) { break }").contains("Expect ';' after 'break'."));
+}
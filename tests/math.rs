@@ -0,0 +1,103 @@
+use ownpy::Engine;
+
+fn eval(source: &str) -> Result<String, String> {
+    let mut engine = Engine::new();
+    match engine.eval(source) {
+        Ok(value) => Ok(value.to_string()),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+fn error(source: &str) -> String {
+    let error = eval(source).unwrap_err();
+    error.lines().last().unwrap().to_string()
+}
+
+#[test]
+fn constants() {
+    assert_eq!(eval("math.pi;").unwrap(), "3.141592653589793");
+    assert_eq!(eval("math.e;").unwrap(), "2.718281828459045");
+    assert_eq!(eval("math.inf;").unwrap(), "inf");
+    assert_eq!(eval("-math.inf < -9223372036854775807;").unwrap(), "true");
+    assert_eq!(eval("math.is_nan(math.nan);").unwrap(), "true");
+    assert_eq!(eval("math.is_nan(1);").unwrap(), "false");
+}
+
+#[test]
+fn float_functions() {
+    assert_eq!(eval("math.sqrt(16);").unwrap(), "4");
+    assert_eq!(eval("type(math.sqrt(16));").unwrap(), "float");
+    assert_eq!(eval("math.sin(0);").unwrap(), "0");
+    assert_eq!(eval("math.atan2(1, 1) * 4 == math.pi;").unwrap(), "true");
+    assert_eq!(eval("math.log(math.e);").unwrap(), "1");
+    assert_eq!(eval("math.log(8, 2);").unwrap(), "3");
+    assert_eq!(eval("math.log10(1000);").unwrap(), "3");
+    assert_eq!(eval("math.log2(1024);").unwrap(), "10");
+}
+
+#[test]
+fn pow_keeps_integers_exact() {
+    assert_eq!(eval("math.pow(2, 10);").unwrap(), "1024");
+    assert_eq!(eval("type(math.pow(2, 10));").unwrap(), "int");
+    assert_eq!(eval("math.pow(2, -1);").unwrap(), "0.5");
+    assert_eq!(eval("type(math.pow(2.0, 3));").unwrap(), "float");
+}
+
+#[test]
+fn rounding_gives_integers() {
+    assert_eq!(eval("math.floor(2.7);").unwrap(), "2");
+    assert_eq!(eval("math.ceil(-2.5);").unwrap(), "-2");
+    assert_eq!(eval("math.round(2.5);").unwrap(), "3");
+    assert_eq!(eval("math.round(-2.5);").unwrap(), "-3");
+    assert_eq!(eval("type(math.floor(5));").unwrap(), "int");
+    assert_eq!(eval("math.round(3.14159, 2);").unwrap(), "3.14");
+}
+
+#[test]
+fn gcd_of_integers() {
+    assert_eq!(eval("math.gcd(12, -18);").unwrap(), "6");
+    assert_eq!(eval("math.gcd(0, 5);").unwrap(), "5");
+}
+
+#[test]
+fn domain_errors() {
+    for source in [
+        "math.sqrt(-1);",
+        "math.log(0);",
+        "math.log(4, 1);",
+        "math.pow(-8, 0.5);",
+        "math.asin(2);",
+    ] {
+        assert!(error(source).ends_with("Math domain error."), "{}", source);
+    }
+}
+
+#[test]
+fn range_and_type_errors() {
+    assert_eq!(
+        error("math.pow(2, 63);"),
+        "Error: math.pow(): Integer overflow."
+    );
+    assert_eq!(
+        error("math.floor(math.inf);"),
+        "Error: math.floor(): Cannot convert inf to int."
+    );
+    assert_eq!(
+        error("math.gcd(1.5, 2);"),
+        "Error: math.gcd(): Expected int for argument 1, found float."
+    );
+    assert_eq!(
+        error("math.sqrt(\"x\");"),
+        "Error: math.sqrt(): Expected float for argument 1, found string."
+    );
+    assert_eq!(
+        error("math.floor(\"x\");"),
+        "Error: math.floor(): Expected number for argument 1, found string."
+    );
+}
+
+#[test]
+fn math_errors_are_catchable() {
+    let source = "var message; try { math.sqrt(-1); } catch (e) { message = e.message; } message;";
+    assert_eq!(eval(source).unwrap(), "math.sqrt(): Math domain error.");
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use ownpy::{arg, Arity, Engine, RuntimeError, Value};
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    let interpreter = engine.interpreter();
+    interpreter.register_fn("double", 1, |args: &[Value]| {
+        let n: i64 = arg(args, 0)?;
+        Ok(Value::Integer(n * 2))
+    });
+    interpreter.register_fn("count", Arity::AtLeast(1), |args: &[Value]| {
+        Ok(Value::Integer(args.len() as i64))
+    });
+    interpreter.register_fn("greet", Arity::Range(0, 1), |args: &[Value]| {
+        let name: Option<String> = arg(args, 0)?;
+        Ok(Value::String(format!(
+            "hello {}",
+            name.unwrap_or_else(|| "world".to_string())
+        )))
+    });
+    engine
+}
+
+#[test]
+fn natives_are_called_like_script_functions() {
+    let mut engine = engine();
+    assert_eq!(engine.eval("double(21);").unwrap(), Value::Integer(42));
+    assert_eq!(
+        engine.eval("var f = double; f(2);").unwrap(),
+        Value::Integer(4)
+    );
+    assert_eq!(
+        engine
+            .eval("fun apply(g, x) { return g(x); } apply(double, 5);")
+            .unwrap(),
+        Value::Integer(10)
+    );
+    assert_eq!(engine.eval("count(1, 2, 3);").unwrap(), Value::Integer(3));
+    assert_eq!(
+        engine.eval("greet() + \", \" + greet(\"ownpy\");").unwrap(),
+        Value::String("hello world, hello ownpy".to_string())
+    );
+    assert_eq!(
+        engine.eval("type(double) + \" \" + str(double);").unwrap(),
+        Value::String("function <native fn double>".to_string())
+    );
+}
+
+#[test]
+fn arity_is_checked_before_the_call() {
+    let mut engine = engine();
+    let error = |engine: &mut Engine, source: &str| engine.eval(source).unwrap_err().to_string();
+    assert!(
+        error(&mut engine, "double();").ends_with("double() takes 1 arguments but 0 were given.")
+    );
+    assert!(error(&mut engine, "count();")
+        .ends_with("count() takes at least 1 arguments but 0 were given."));
+    assert!(error(&mut engine, "double(a: 1);")
+        .ends_with("double() got an unexpected keyword argument 'a'."));
+}
+
+#[test]
+fn conversion_errors_name_the_argument() {
+    let mut engine = engine();
+    let error = engine.eval("double(\"x\");").unwrap_err().to_string();
+    assert!(
+        error.ends_with("double(): Expected int for argument 1, found string."),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn native_errors_are_catchable_and_located_at_the_call() {
+    let (mut engine, output) = common::engine();
+    engine.interpreter().register_fn("fail", 0, |_: &[Value]| {
+        Err(RuntimeError::new("nope".to_string(), 0))
+    });
+    engine
+        .eval("try {\n  fail();\n} catch (e) { print e.message; print e.line; }")
+        .unwrap();
+    assert_eq!(output.text(), "fail(): nope\n2\n");
+    let error = engine.eval("\n\nfail();").unwrap_err().to_string();
+    assert!(error.contains("line 3, in <script>"), "{}", error);
+}
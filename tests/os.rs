@@ -0,0 +1,92 @@
+mod common;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use common::{error, run};
+use ownpy::{Capabilities, Engine, Value};
+
+#[test]
+fn environment_variables_round_trip() {
+    let source = "
+        print os.getenv(\"OWNPY_TEST_UNSET\");
+        os.setenv(\"OWNPY_TEST_SET\", \"value\");
+        print os.getenv(\"OWNPY_TEST_SET\");
+    ";
+    assert_eq!(run(source).unwrap(), "nil\nvalue\n");
+    assert_eq!(std::env::var("OWNPY_TEST_SET").unwrap(), "value");
+}
+
+#[test]
+fn invalid_environment_variables_are_errors() {
+    assert!(
+        error("os.setenv(\"\", \"x\");").ends_with("os.setenv(): Invalid environment variable ''.")
+    );
+    assert!(error("os.setenv(\"A=B\", \"x\");")
+        .ends_with("os.setenv(): Invalid environment variable 'A=B'."));
+}
+
+#[test]
+fn time_is_seconds_since_the_epoch() {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs_f64();
+    match Engine::new().eval("os.time();").unwrap() {
+        Value::Float(time) => assert!((time - now).abs() < 60.0, "{} vs {}", time, now),
+        other => panic!("expected a float, got {}", other),
+    }
+}
+
+#[test]
+fn timestamps_are_formatted() {
+    assert_eq!(
+        run("print os.format_utc(0);").unwrap(),
+        "1970-01-01 00:00:00\n"
+    );
+    assert_eq!(
+        run("print os.format_utc(86400.5, \"%Y/%m/%d %H:%M\");").unwrap(),
+        "1970/01/02 00:00\n"
+    );
+    assert_eq!(
+        run("print len(os.format_time(os.time()));").unwrap(),
+        "19\n"
+    );
+    assert!(
+        error("os.format_utc(0, \"%Q\");").ends_with("os.format_utc(): Invalid time format '%Q'.")
+    );
+    assert!(error("os.format_utc(math.inf);").ends_with("out of range."));
+}
+
+#[test]
+fn monotonic_time_advances_across_sleep() {
+    let source = "var start = os.monotonic(); os.sleep(0.01); os.monotonic() - start >= 0.01;";
+    assert_eq!(Engine::new().eval(source).unwrap(), Value::Bool(true));
+    assert!(error("os.sleep(-1);").ends_with("os.sleep(): Invalid sleep duration -1."));
+}
+
+#[test]
+fn cwd_is_the_working_directory() {
+    let cwd = std::env::current_dir().unwrap();
+    assert_eq!(
+        Engine::new().eval("os.cwd();").unwrap(),
+        Value::String(cwd.to_string_lossy().into_owned())
+    );
+}
+
+#[test]
+fn run_captures_output_and_exit_code() {
+    let source = "
+        var result = os.run(\"sh\", \"-c|echo out; echo err >&2; exit 3\".split(\"|\"));
+        print result.stdout + result.stderr + str(result.code);
+    ";
+    assert_eq!(run(source).unwrap(), "out\nerr\n3\n");
+    assert!(error("os.run(\"ownpy-no-such-program\");")
+        .contains("os.run(): Cannot run 'ownpy-no-such-program'"));
+}
+
+#[test]
+fn sandboxed_engines_have_no_os() {
+    let mut engine = Engine::with_capabilities(Capabilities::sandboxed());
+    let error = engine.eval("os.getenv(\"HOME\");").unwrap_err().to_string();
+    assert!(error.ends_with("Undefined variable 'os'."), "{}", error);
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use std::io::{self, Write};
+use std::process::Command;
+
+use ownpy::Engine;
+
+#[test]
+fn print_writes_to_the_sink() {
+    let (mut engine, output) = common::engine();
+    engine.eval("print \"one\"; print 2; print nil;").unwrap();
+    assert_eq!(output.text(), "one\n2\nnil\n");
+}
+
+#[test]
+fn diagnostics_stay_out_of_the_sink() {
+    let (mut engine, output) = common::engine();
+    assert!(engine.eval("print 1; missing;").is_err());
+    assert!(engine.eval("print (;").is_err());
+    assert_eq!(output.text(), "1\n");
+}
+
+#[test]
+fn engines_write_to_their_own_sinks() {
+    let (mut first, first_output) = common::engine();
+    let (mut second, second_output) = common::engine();
+    first.eval("print \"first\";").unwrap();
+    second.eval("print \"second\";").unwrap();
+    assert_eq!(first_output.text(), "first\n");
+    assert_eq!(second_output.text(), "second\n");
+}
+
+struct Closed;
+
+impl Write for Closed {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn write_failures_are_runtime_errors() {
+    let mut engine = Engine::new();
+    engine.set_output(Closed);
+    let error = engine.eval("print 1;").unwrap_err().to_string();
+    assert!(
+        error.ends_with("Error: Failed to write output: closed"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn running_a_script_prints_only_its_output() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ownpy"))
+        .args(["-e", "var x = 1; print x + 1;"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "2\n");
+    assert!(output.stderr.is_empty());
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::{error, run};
+
+#[test]
+fn defaults_fill_missing_arguments() {
+    let source = "
+        fun f(a, b = 2) { return a + b; }
+        print f(1);
+        print f(1, 5);
+    ";
+    assert_eq!(run(source).unwrap(), "3\n6\n");
+}
+
+#[test]
+fn keyword_arguments_bind_by_name() {
+    let source = "
+        fun f(a, b = 2, c = 3) { return str(a) + str(b) + str(c); }
+        print f(b: 5, a: 1);
+        print f(1, c: 9);
+    ";
+    assert_eq!(run(source).unwrap(), "153\n129\n");
+}
+
+#[test]
+fn rest_parameters_collect_extras() {
+    let source = "
+        fun f(first, ...rest) { print first; print len(rest); return rest; }
+        print f(1, 2, 3)[1];
+        print f(1);
+    ";
+    assert_eq!(run(source).unwrap(), "1\n2\n3\n1\n0\n[]\n");
+}
+
+#[test]
+fn arity_errors_name_the_parameter() {
+    assert!(error("fun f(a, b) {}\nf(1);").contains("f() missing argument for parameter 'b'."));
+    assert!(
+        error("fun f(a) {}\nf(1, c: 2);").contains("f() got an unexpected keyword argument 'c'.")
+    );
+    assert!(
+        error("fun f(a) {}\nf(1, a: 2);").contains("f() got multiple values for parameter 'a'.")
+    );
+    assert!(error("fun f(a) {}\nf(1, 2);")
+        .contains("f() takes 1 positional arguments but 2 were given."));
+}
+
+#[test]
+fn arity_errors_point_at_the_call() {
+    let message = error("fun f(a, b) {}\n\nf(1);");
+    assert!(message.contains("line 3, in <script>"), "{}", message);
+}
+
+#[test]
+fn malformed_parameter_lists_are_syntax_errors() {
+    assert_eq!(
+        error("fun f(a = 1, b) {}"),
+        "[line 1] Error at 'b': Parameter without a default can't follow one with a default."
+    );
+    assert_eq!(
+        error("fun f(...rest, a) {}"),
+        "[line 1] Error at ',': Expect end of parameter list."
+    );
+    assert_eq!(
+        error("fun f(a) {}\nf(a: 1, 2);"),
+        "[line 2] Error at '2': Positional argument can't follow keyword arguments."
+    );
+}
@@ -0,0 +1,98 @@
+use ownpy::{formatter, lint::Linter, printer, Engine, Parser, Scanner};
+use std::process::{Command, Output};
+
+fn run(source: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ownpy"))
+        .args(["-e", source])
+        .output()
+        .unwrap()
+}
+
+// A function that recurses `depth` times, with its recursive call nested
+// inside `blocks` blocks so each call takes more native stack.
+fn recursion(blocks: usize, depth: usize) -> String {
+    format!(
+        "fun r(n) {{ if (n == 0) return 0; {}return r(n - 1);{} }} print r({});",
+        "{ ".repeat(blocks),
+        " }".repeat(blocks),
+        depth
+    )
+}
+
+#[test]
+fn recursing_just_under_the_call_limit_succeeds() {
+    let output = run(&recursion(30, 255));
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "0\n");
+}
+
+#[test]
+fn recursing_past_the_call_limit_is_a_runtime_error() {
+    let output = run(&recursion(0, 256));
+    assert_eq!(output.status.code(), Some(70));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("maximum call depth of 256 exceeded"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn nesting_too_deep_is_a_runtime_error() {
+    let output = run(&recursion(100, 250));
+    assert_eq!(output.status.code(), Some(70));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("maximum nesting depth"), "{}", stderr);
+}
+
+#[test]
+fn parsing_too_deep_is_a_syntax_error() {
+    let source = format!("print {}1{};", "(".repeat(20_000), ")".repeat(20_000));
+    let output = run(&source);
+    assert_eq!(output.status.code(), Some(65));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Too much nesting."), "{}", stderr);
+}
+
+// The rest run in-process on the test harness's ordinary thread, as an
+// embedding host would.
+fn eval(source: &str) -> Result<String, String> {
+    let mut engine = Engine::new();
+    engine.set_output(Vec::new());
+    match engine.eval(source) {
+        Ok(value) => Ok(value.to_string()),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+#[test]
+fn engine_recursion_just_under_the_call_limit_succeeds() {
+    let source = recursion(8, 255).replace("print r(255);", "r(255);");
+    assert_eq!(eval(&source).unwrap(), "0");
+    let source = recursion(30, 255).replace("print r(255);", "r(255);");
+    assert_eq!(eval(&source).unwrap(), "0");
+}
+
+#[test]
+fn engine_nesting_too_deep_is_a_runtime_error() {
+    let error = eval(&recursion(100, 250)).unwrap_err();
+    assert!(error.contains("maximum nesting depth"), "{}", error);
+}
+
+#[test]
+fn engine_parsing_too_deep_is_a_syntax_error() {
+    let source = format!("{}1{};", "(".repeat(20_000), ")".repeat(20_000));
+    let error = eval(&source).unwrap_err();
+    assert!(error.contains("Too much nesting."), "{}", error);
+}
+
+#[test]
+fn tools_walk_the_deepest_trees_the_parser_accepts() {
+    let source = format!("print {}1{};", "(".repeat(250), ")".repeat(250));
+    let tokens = Scanner::new(source.clone()).scan_tokens().unwrap();
+    let statements = Parser::new(tokens).parse().unwrap();
+    assert!(printer::sexpr(&statements).contains("group"));
+    assert!(formatter::format(&source).is_ok());
+    assert!(Linter::default().lint(&source).is_ok());
+}
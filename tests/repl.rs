@@ -0,0 +1,154 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+// Runs the REPL on `input` with a home directory unique to each test, so the
+// history file lands somewhere the test can read.
+fn repl(name: &str, input: &str) -> (Output, PathBuf) {
+    let home = std::env::temp_dir().join(format!("ownpy-repl-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&home);
+    fs::create_dir_all(&home).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ownpy"))
+        .env("HOME", &home)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    (child.wait_with_output().unwrap(), home)
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn blocks_continue_across_lines() {
+    let input = "fun f() {\n  return 1;\n}\nprint f();\nvar s = \"a\nb\";\nprint s;\n";
+    let (output, home) = repl("multiline", input);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "1\na\nb\n");
+    fs::remove_dir_all(home).unwrap();
+}
+
+#[test]
+fn history_is_saved_per_entry() {
+    let (output, home) = repl("history", "fun f() {\n}\n\nprint 1;\n");
+    assert_eq!(output.status.code(), Some(0));
+    let history = fs::read_to_string(home.join(".ownpy_history")).unwrap();
+    let entries: Vec<&str> = history.lines().skip(1).collect();
+    assert_eq!(entries, ["fun f() {\\n}", "print 1;"]);
+    fs::remove_dir_all(home).unwrap();
+}
+
+#[test]
+fn errors_do_not_end_the_session() {
+    let (output, home) = repl(
+        "errors",
+        "print missing;\nprint +;\nprint \"still here\";\n",
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "still here\n");
+    assert!(stderr(&output).contains("Undefined variable 'missing'."));
+    assert!(stderr(&output).contains("Expect expression."));
+    fs::remove_dir_all(home).unwrap();
+}
+
+#[test]
+fn end_of_input_ends_the_session() {
+    // An unfinished block at end of input is dropped rather than run.
+    let (output, home) = repl("eof", "print 1;\nfun f() {\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "1\n");
+    fs::remove_dir_all(home).unwrap();
+}
+
+#[test]
+fn exit_ends_the_session_with_its_code() {
+    let (output, home) = repl("exit", "exit(3);\nprint 1;\n");
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(stdout(&output), "");
+    fs::remove_dir_all(home).unwrap();
+}
+
+#[test]
+fn expression_values_are_printed() {
+    let input = "1 + 2\n\"text\";\nnil\nvar x = 4\nx * 2\nprint x;\n";
+    let (output, home) = repl("autoprint", input);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "3\ntext\n8\n4\n");
+    fs::remove_dir_all(home).unwrap();
+}
+
+#[test]
+fn env_and_reset_show_and_forget_globals() {
+    let input = "var b = 2;\nfun a() {}\n:env\n:reset\n:env\nb\n";
+    let (output, home) = repl("env", input);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "a = <fn a>\nb = 2\n");
+    assert!(stderr(&output).contains("Undefined variable 'b'."));
+    fs::remove_dir_all(home).unwrap();
+}
+
+#[test]
+fn tokens_and_ast_show_the_parse() {
+    let (output, home) = repl("parse", ":tokens 1 +\n:ast 1 + 2\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        stdout(&output),
+        "   1  Number 1\n   1  Plus +\n   1  Eof \n(expr (+ 1 2))\n"
+    );
+    fs::remove_dir_all(home).unwrap();
+}
+
+#[test]
+fn load_runs_a_file_in_the_session() {
+    let home = std::env::temp_dir().join(format!("ownpy-repl-script-{}", std::process::id()));
+    fs::create_dir_all(&home).unwrap();
+    let script = home.join("loaded.ownpy");
+    fs::write(&script, "var loaded = 42;\n").unwrap();
+    let input = format!(
+        ":load {}\nloaded\n:load {}\n",
+        script.display(),
+        home.join("missing").display()
+    );
+    let (output, repl_home) = repl("load", &input);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "42\n");
+    assert!(stderr(&output).contains("Cannot read '"));
+    fs::remove_dir_all(home).unwrap();
+    fs::remove_dir_all(repl_home).unwrap();
+}
+
+#[test]
+fn time_reports_elapsed_time() {
+    let (output, home) = repl("time", ":time 1 + 1\n");
+    assert_eq!(output.status.code(), Some(0));
+    let text = stdout(&output);
+    assert!(text.starts_with("2\nElapsed: "), "{}", text);
+    assert!(text.ends_with(" ms\n"), "{}", text);
+    fs::remove_dir_all(home).unwrap();
+}
+
+#[test]
+fn help_and_bad_commands() {
+    let (output, home) = repl("help", ":help\n:bogus\n:load\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains(":env            list the globals"));
+    assert_eq!(
+        stderr(&output),
+        "Unknown command ':bogus'. Type :help for a list of commands.\nUsage: :load <file>\n"
+    );
+    fs::remove_dir_all(home).unwrap();
+}
@@ -0,0 +1,34 @@
+use ownpy::Engine;
+
+fn eval(source: &str) -> String {
+    let mut engine = Engine::new();
+    match engine.eval(source) {
+        Ok(value) => value.to_string(),
+        Err(error) => error.to_string(),
+    }
+}
+
+#[test]
+fn huge_repeats_are_catchable_errors() {
+    let caught = eval(
+        r#"var message; try { "ab".repeat(9000000000000000000); } catch (e) { message = e; } message;"#,
+    );
+    assert_eq!(caught, "repeat(): Result too large.");
+}
+
+#[test]
+fn huge_padding_is_an_error() {
+    for method in ["pad_left", "pad_right"] {
+        let error = eval(&format!(r#""ab".{}(9000000000000000000, "é");"#, method));
+        assert!(error.contains("Result too large."), "{}", error);
+    }
+}
+
+#[test]
+fn small_repeats_and_padding_still_work() {
+    assert_eq!(eval(r#""ab".repeat(3);"#), "ababab");
+    assert_eq!(
+        eval(r#""ab".pad_left(4, "-") + "ab".pad_right(3);"#),
+        "--abab "
+    );
+}